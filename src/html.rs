@@ -0,0 +1,325 @@
+//! Standalone HTML report, which embeds all styles and charts so that it can be shared as a single file
+
+use super::{
+    aggregate_stats, format_busiest_day, format_commits, overview_rows, print_stats,
+    print_submodules, symbol_index, symbol_thresholds, top_contributors, AppArgs, RepositoryResult,
+    CHECKERBOARD_SYMBOL_AMOUNT, PIE_CHART_COLORS,
+};
+use chrono::{Datelike as DL, NaiveDate};
+use repolyzer::{RepositoryStats, DAYS_IN_GRAPH, MAX_DAYS_IN_YEAR};
+use std::f64::consts::PI;
+use std::io::{self, Write};
+
+/// Colors of the heatmap cells, from no commits to the most commits a day (same steps as the checker board symbols)
+const HEATMAP_COLORS: [&str; CHECKERBOARD_SYMBOL_AMOUNT] =
+    ["#ebedf0", "#9be9a8", "#40c463", "#30a14e", "#216e39"];
+
+const STYLE: &str = "body { font-family: sans-serif; margin: 2em auto; max-width: 75em; color: #24292f; }
+h1, h2 { border-bottom: 1px solid #d0d7de; padding-bottom: 0.3em; }
+table.overview td { padding: 0.2em 1em 0.2em 0; }
+table.overview td:first-child { font-weight: bold; }
+.pie { display: flex; align-items: center; gap: 2em; }
+.pie ul { list-style: none; padding: 0; }
+.swatch { display: inline-block; width: 1em; height: 1em; margin-right: 0.5em; vertical-align: middle; }
+table.heatmap { border-spacing: 2px; font-size: 0.7em; }
+table.heatmap td { width: 10px; height: 10px; padding: 0; }
+table.heatmap th { font-weight: normal; text-align: left; }
+pre { background: #f6f8fa; padding: 1em; overflow-x: auto; }";
+
+/// Prints the whole document with the stats of all repositories, followed by a summary if there are multiple
+pub(crate) fn print_report(
+    out: &mut dyn Write,
+    results: &[RepositoryResult],
+    app_args: &AppArgs,
+) -> io::Result<()> {
+    writeln!(out, "<!DOCTYPE html>")?;
+    writeln!(out, "<html lang=\"en\">")?;
+    writeln!(out, "<head>")?;
+    writeln!(out, "<meta charset=\"utf-8\">")?;
+    writeln!(out, "<title>Repolyzer report</title>")?;
+    writeln!(out, "<style>\n{}\n</style>", STYLE)?;
+    writeln!(out, "</head>")?;
+    writeln!(out, "<body>")?;
+    for result in results {
+        writeln!(
+            out,
+            "<h1>Repository: {}</h1>",
+            escape_html(&result.location.to_string())
+        )?;
+        print_stats(out, &result.stats, app_args, result.has_no_commits)?;
+        print_submodules(out, result, app_args)?;
+    }
+    if results.len() > 1 {
+        let summary = aggregate_stats(results.iter().flat_map(RepositoryResult::all_stats));
+        writeln!(
+            out,
+            "<h1>Summary of all {} repositories</h1>",
+            results.len()
+        )?;
+        print_overview(
+            out,
+            &summary,
+            app_args.analysis.extended_stats,
+            &app_args.date_format,
+        )?;
+    }
+    writeln!(out, "</body>")?;
+    writeln!(out, "</html>")
+}
+
+pub(crate) fn print_overview(
+    out: &mut dyn Write,
+    stats: &RepositoryStats,
+    extended: bool,
+    date_format: &str,
+) -> io::Result<()> {
+    writeln!(out, "<h2>Overall commit stats</h2>")?;
+    writeln!(out, "<table class=\"overview\">")?;
+    for (label, value) in overview_rows(stats, extended, date_format) {
+        writeln!(
+            out,
+            "<tr><td>{}</td><td>{}</td></tr>",
+            escape_html(label.trim_end_matches(['.', ' '])),
+            escape_html(&value)
+        )?;
+    }
+    writeln!(out, "</table>")
+}
+
+/// Prints the pie chart as an inline SVG, with the same colors as in the terminal
+pub(crate) fn print_pie_chart(
+    out: &mut dyn Write,
+    stats: &RepositoryStats,
+    named: usize,
+    min_commits: usize,
+) -> io::Result<()> {
+    writeln!(out, "<h2>Commit pie chart</h2>")?;
+    let top_contributors = top_contributors(stats, named, min_commits);
+    if top_contributors.is_empty() {
+        return writeln!(out, "<p>No commits to chart!</p>");
+    }
+
+    let total: f64 = top_contributors.iter().map(|(_, commits)| commits).sum();
+    let color = |i: usize| {
+        let (r, g, b) = PIE_CHART_COLORS[i % PIE_CHART_COLORS.len()];
+        format!("rgb({}, {}, {})", r, g, b)
+    };
+    writeln!(out, "<div class=\"pie\">")?;
+    writeln!(
+        out,
+        "<svg viewBox=\"-1 -1 2 2\" width=\"240\" height=\"240\" role=\"img\">"
+    )?;
+    if let [_] = top_contributors.as_slice() {
+        // A single slice would be an arc from and to the same point, which isn't drawn
+        writeln!(out, "<circle r=\"1\" fill=\"{}\"/>", color(0))?;
+    } else {
+        // Start at the top and go clockwise
+        let mut angle = -PI / 2.0;
+        for (i, (_, commits)) in top_contributors.iter().enumerate() {
+            let sweep = commits / total * 2.0 * PI;
+            let large_arc = u8::from(sweep > PI);
+            writeln!(
+                out,
+                "<path d=\"M 0 0 L {:.4} {:.4} A 1 1 0 {} 1 {:.4} {:.4} Z\" fill=\"{}\"/>",
+                angle.cos(),
+                angle.sin(),
+                large_arc,
+                (angle + sweep).cos(),
+                (angle + sweep).sin(),
+                color(i)
+            )?;
+            angle += sweep;
+        }
+    }
+    writeln!(out, "</svg>")?;
+
+    writeln!(out, "<ul>")?;
+    for (i, (name, commits)) in top_contributors.iter().enumerate() {
+        writeln!(
+            out,
+            "<li><span class=\"swatch\" style=\"background: {}\"></span>{}: {} ({:.1}%)</li>",
+            color(i),
+            escape_html(name),
+            format_commits(*commits),
+            commits / total * 100.0
+        )?;
+    }
+    writeln!(out, "</ul>")?;
+    writeln!(out, "</div>")
+}
+
+/// Prints the commits per day of the last year as a table with a colored cell per day
+pub(crate) fn print_commit_graph(out: &mut dyn Write, stats: &RepositoryStats) -> io::Result<()> {
+    let first_day = stats.today() - chrono::Duration::days(DAYS_IN_GRAPH as i64 - 1);
+    writeln!(out, "<h2>Commit graph</h2>")?;
+    writeln!(
+        out,
+        "<p>Commits in the last year: {} | Longest Streak: {} days | Current Streak: {} days | Max a day: {} | Busiest day: {}</p>",
+        stats.commits_last_year,
+        stats.longest_commit_streak,
+        stats.current_commit_streak,
+        stats.max_commits_a_day,
+        format_busiest_day(stats)
+    )?;
+    let distribution = symbol_thresholds(stats.max_commits_a_day);
+    print_heatmap_table(
+        out,
+        &stats.commits_per_day_last_year,
+        first_day,
+        &distribution,
+    )
+}
+
+/// Prints a commit graph for every year of the whole history, all sharing the same colors
+pub(crate) fn print_all_time_heatmap(
+    out: &mut dyn Write,
+    stats: &RepositoryStats,
+) -> io::Result<()> {
+    let max_commits_a_day = stats
+        .commits_per_day_by_year
+        .values()
+        .flat_map(|days| days.iter().copied())
+        .max()
+        .unwrap_or(0);
+    let distribution = symbol_thresholds(max_commits_a_day);
+
+    writeln!(out, "<h2>Commit graph of every year</h2>")?;
+    let mut years: Vec<(&i32, &[usize; MAX_DAYS_IN_YEAR])> =
+        stats.commits_per_day_by_year.iter().collect();
+    years.sort_by_key(|(year, _)| **year);
+    for (year, days) in years {
+        let first_day = NaiveDate::from_ymd_opt(*year, 1, 1).unwrap();
+        let last_day = NaiveDate::from_ymd_opt(*year, 12, 31).unwrap();
+        let days = &days[..last_day.ordinal() as usize];
+        writeln!(
+            out,
+            "<h3>{}</h3>\n<p>Commits: {} | Max a day: {}</p>",
+            year,
+            days.iter().sum::<usize>(),
+            days.iter().max().unwrap()
+        )?;
+        print_heatmap_table(out, days, first_day, &distribution)?;
+    }
+    Ok(())
+}
+
+/// Prints the commits per day starting at `first_day` as a table with a column per week and a row per weekday
+fn print_heatmap_table(
+    out: &mut dyn Write,
+    days: &[usize],
+    first_day: NaiveDate,
+    distribution: &[usize; CHECKERBOARD_SYMBOL_AMOUNT],
+) -> io::Result<()> {
+    // Each column is a week from Monday to Sunday, so the first column may start with empty cells
+    let offset = first_day.weekday().num_days_from_monday() as usize;
+    let weeks = (offset + days.len()).div_ceil(7);
+    let day_at = |week: usize, weekday: usize| {
+        (week * 7 + weekday)
+            .checked_sub(offset)
+            .filter(|index| *index < days.len())
+    };
+
+    writeln!(out, "<table class=\"heatmap\">")?;
+    // Label the weeks in which a month starts
+    write!(out, "<tr><th></th>")?;
+    for week in 0..weeks {
+        let month_start = (0..7)
+            .filter_map(|weekday| day_at(week, weekday))
+            .map(|index| first_day + chrono::Duration::days(index as i64))
+            .find(|date| date.day() == 1);
+        match month_start {
+            Some(date) => write!(out, "<th>{}</th>", date.format("%b"))?,
+            None => write!(out, "<th></th>")?,
+        }
+    }
+    writeln!(out, "</tr>")?;
+    for (weekday, name) in ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"]
+        .iter()
+        .enumerate()
+    {
+        write!(out, "<tr><th>{}</th>", name)?;
+        for week in 0..weeks {
+            match day_at(week, weekday) {
+                Some(index) => {
+                    let date = first_day + chrono::Duration::days(index as i64);
+                    write!(
+                        out,
+                        "<td style=\"background: {}\" title=\"{}: {} commits\"></td>",
+                        HEATMAP_COLORS[symbol_index(days[index], distribution)],
+                        date.format("%Y-%m-%d"),
+                        days[index]
+                    )?;
+                }
+                None => write!(out, "<td></td>")?,
+            }
+        }
+        writeln!(out, "</tr>")?;
+    }
+    writeln!(out, "</table>")
+}
+
+/// Prints the output of a text printer as a preformatted block, using its title as heading
+pub(crate) fn print_pre_section(
+    out: &mut dyn Write,
+    printer: impl FnOnce(&mut dyn Write) -> io::Result<()>,
+) -> io::Result<()> {
+    let mut buffer: Vec<u8> = Vec::new();
+    printer(&mut buffer)?;
+    let text = String::from_utf8_lossy(&buffer);
+
+    // The separator lines are only needed in the text output
+    let mut lines = text
+        .lines()
+        .filter(|line| line.is_empty() || !line.chars().all(|c| c == '-'))
+        .peekable();
+    if let Some(title) = lines.next_if(|line| line.ends_with(':')) {
+        writeln!(out, "<h2>{}</h2>", escape_html(title.trim_end_matches(':')))?;
+    }
+    writeln!(out, "<pre>")?;
+    for line in lines {
+        writeln!(out, "{}", escape_html(line))?;
+    }
+    writeln!(out, "</pre>")
+}
+
+/// Escapes the characters with a special meaning in HTML text and attributes
+pub(crate) fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn html_escaping() {
+        assert_eq!(
+            escape_html("<b>\"Tom\" & 'Jerry'</b>"),
+            "&lt;b&gt;&quot;Tom&quot; &amp; &#39;Jerry&#39;&lt;/b&gt;"
+        );
+    }
+
+    #[test]
+    fn heatmap_table_starts_at_weekday_of_first_day() {
+        // 2024-01-03 is a Wednesday, so Monday and Tuesday of the first week are empty
+        let first_day = NaiveDate::from_ymd_opt(2024, 1, 3).unwrap();
+        let days = [0, 1, 2, 3, 4, 5, 6, 7];
+        let mut out = Vec::new();
+        print_heatmap_table(&mut out, &days, first_day, &symbol_thresholds(8)).unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        let rows: Vec<&str> = out
+            .lines()
+            .filter(|line| line.starts_with("<tr><th>") && !line.starts_with("<tr><th></th>"))
+            .collect();
+        assert_eq!(rows.len(), 7);
+        assert!(rows[0].starts_with("<tr><th>Mon</th><td></td><td style="));
+        assert!(rows[2].contains("title=\"2024-01-03: 0 commits\""));
+        assert_eq!(out.matches("<td style=").count(), days.len());
+    }
+}
@@ -0,0 +1,2408 @@
+//! Analysis of Git repositories, as used by the `repolyzer` command line tool.
+//!
+//! The entry point is [`analyze`], which walks the history of a [`Repository`] and
+//! collects the [`RepositoryStats`] that are enabled in the [`AnalysisOptions`].
+use chrono::{DateTime as DT, Datelike as DL, FixedOffset, Local, Months, NaiveDate, Timelike};
+use git2::{
+    BranchType, ErrorCode, ObjectType, Oid, Repository, Sort, TreeWalkMode, TreeWalkResult,
+};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use icu_normalizer::ComposingNormalizerBorrowed;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt;
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// ------------------------- Constants
+const UNKNOWN_AUTHOR: &str = ">UNKNOWN<";
+const NO_EXTENSION: &str = "(none)";
+const ROOT_DIRECTORY: &str = "(root)";
+/// Lines of commit messages longer than this are considered too long
+pub const MAX_MESSAGE_LINE_LENGTH: usize = 72;
+const CONVENTIONAL_COMMIT_TYPES: [&str; 8] = [
+    "feat", "fix", "docs", "chore", "refactor", "test", "style", "perf",
+];
+/// The days since the same date a year ago (exclusive) up until today, which are 366 if they span a Feb 29
+pub const DAYS_IN_GRAPH: usize = 366;
+/// Days of a leap year
+pub const MAX_DAYS_IN_YEAR: usize = 366;
+/// Days of the spans whose commits are compared for the trend of the activity
+pub const QUARTER_DAYS: i64 = 90;
+/// Commits changing more files are left out of the coupling, as they would pair up every two of them (e.g. reformats)
+pub const MAX_COUPLED_FILES: usize = 50;
+// Exit codes of the command line tool, see [`RepolyzerError::exit_code`]
+/// Invalid arguments or configuration file
+pub const EXIT_USAGE: i32 = 1;
+/// The local repository could not be opened
+pub const EXIT_REPO_OPEN: i32 = 2;
+/// The remote repository could not be cloned
+pub const EXIT_CLONE: i32 = 3;
+/// The ref to start from could not be resolved
+pub const EXIT_REF_NOT_FOUND: i32 = 4;
+/// None of the analyzed repositories has any (matching) commits
+pub const EXIT_NO_COMMITS: i32 = 5;
+/// The stats could not be written
+pub const EXIT_OUTPUT: i32 = 6;
+// -------------------------
+
+/// Controls which commits are analyzed and which of the optional stats are computed
+#[derive(Default, Clone, Debug)]
+pub struct AnalysisOptions {
+    // Optional stats
+    pub extended_stats: bool,
+    pub commit_graph: bool,
+    pub all_time_heatmap: bool,
+    pub weekday_stats: bool,
+    pub hour_stats: bool,
+    pub message_stats: bool,
+    pub signature_stats: bool,
+    pub lang_stats: bool,
+    pub dir_stats: bool,
+    pub size_histogram: bool,
+    pub hotspots: bool,
+    pub tag_stats: bool,
+    pub branch_stats: bool,
+    pub growth_stats: bool,
+    pub author_timeline: bool,
+    /// Split the contributors into core ones with at least this many commits (or a recent one) and occasional ones
+    pub contributor_classes: Option<usize>,
+    /// List this many of the largest files in the tree of the analyzed ref
+    pub largest_files: Option<usize>,
+    /// List this many of the pairs of files that were changed together by the most commits
+    pub coupling: Option<usize>,
+    pub repo_size: bool,
+
+    // Filters
+    /// Branch, tag or revision to start from instead of HEAD
+    pub reference: Option<String>,
+    /// Range "<base>..<head>" of the commits reachable from head, but not from base (instead of `reference`)
+    pub compare: Option<String>,
+    /// Any gitrevisions spec, e.g. "HEAD~100..HEAD" or "main...feature" (instead of `reference` and `compare`)
+    pub range: Option<String>,
+    /// Only commits whose author name or email contains this (case-insensitive)
+    pub author: Option<String>,
+    /// Skip commits whose author name or email contains any of these (case-insensitive)
+    pub exclude_authors: Vec<String>,
+    /// Only commits made at or after this Unix timestamp
+    pub since: Option<i64>,
+    /// Only commits made at or before this Unix timestamp
+    pub until: Option<i64>,
+    /// Only the most recent n (matching) commits
+    pub limit: Option<usize>,
+    /// Only commits changing files in these pathspecs (e.g. "services/payments/"), and only their changes there
+    pub paths: Vec<String>,
+    /// Skip merge commits, so that only the changes of the merged commits themselves are counted
+    pub no_merges: bool,
+    /// Only walk the first parent of merge commits, leaving out the commits of merged branches
+    pub first_parent: bool,
+    /// Skip commits without changes (e.g. made with `--allow-empty`), which only inflate the counts
+    pub no_empty: bool,
+    /// Globs of files (e.g. lockfiles) whose changes are left out of the changed lines stats
+    pub ignore_paths: Vec<String>,
+
+    /// How co-authors from `Co-authored-by:` trailers are credited
+    pub coauthors: CoauthorMode,
+    /// Collapse the whitespace in contributor names and bring them into Unicode NFC, so that spellings count as one
+    pub normalize_authors: bool,
+    /// Also lowercase the contributor names (only with `normalize_authors`)
+    pub lowercase_authors: bool,
+    /// Whether the commits are credited to their author or committer
+    pub identity: Identity,
+    /// Whether merge commits are part of the changed lines stats
+    pub churn_merges: ChurnMerges,
+    /// How the changed lines of a commit are matched up
+    pub diff_algorithm: DiffAlgorithm,
+    /// Lines that only differ in whitespace are not counted as changed (like `git diff -w`)
+    pub ignore_whitespace: bool,
+    /// Inserted or removed blank lines (or ones with only whitespace) are not counted as changed
+    pub ignore_blank_lines: bool,
+    /// Only diff this share (0 to 1) of the commits and extrapolate the changed lines stats from them
+    pub sample_rate: Option<f64>,
+
+    /// Fixed current time (seconds since the epoch) instead of the clock, e.g. for reproducible output
+    pub fixed_now: Option<i64>,
+
+    /// Show a progress counter on stderr (only if it is a terminal)
+    pub progress: bool,
+    /// Show the commit amount and the last commit found so far instead of the plain counter while walking
+    pub live_stats: bool,
+}
+
+impl AnalysisOptions {
+    /// The current time in seconds since the epoch, which the last year of the commit graph ends at
+    pub fn now(&self) -> i64 {
+        self.fixed_now.unwrap_or_else(|| Local::now().timestamp())
+    }
+
+    /// Whether any of the enabled stats needs the changed lines of every commit
+    fn diffs_commits(&self) -> bool {
+        self.extended_stats
+            || self.lang_stats
+            || self.dir_stats
+            || self.size_histogram
+            || self.hotspots
+            || self.coupling.is_some()
+    }
+}
+
+/// How co-authors from `Co-authored-by:` trailers are credited in the contributors
+#[derive(Default, Clone, Copy, Debug, PartialEq)]
+pub enum CoauthorMode {
+    /// Only the author of the commit is credited
+    #[default]
+    Ignore,
+    /// The author and every co-author are credited with a full commit
+    Full,
+    /// The commit is split evenly between the author and the co-authors
+    Split,
+}
+
+/// How the changes of merge commits count towards the changed lines stats
+#[derive(Default, Clone, Copy, Debug, PartialEq)]
+pub enum ChurnMerges {
+    /// Merges are not diffed, as their changes were already counted on the merged branches
+    #[default]
+    Skip,
+    /// Merges are diffed against their first parent, which repeats all changes of the merged branches
+    FirstParent,
+}
+
+/// Algorithm that diffs the files for the changed lines stats, with the same results as the one of `git diff`
+#[derive(Default, Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffAlgorithm {
+    /// The default of git
+    #[default]
+    Myers,
+    /// Only matches lines that are unique in both files first, which keeps moved blocks together
+    Patience,
+    /// Spends extra time to find the smallest diff
+    Minimal,
+}
+
+impl fmt::Display for DiffAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DiffAlgorithm::Myers => write!(f, "myers"),
+            DiffAlgorithm::Patience => write!(f, "patience"),
+            DiffAlgorithm::Minimal => write!(f, "minimal"),
+        }
+    }
+}
+
+/// Which identity of a commit is credited as its contributor
+#[derive(Default, Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Identity {
+    /// Whoever wrote the changes
+    #[default]
+    Author,
+    /// Whoever created the commit, e.g. by rebasing or cherry-picking it
+    Committer,
+}
+
+impl fmt::Display for Identity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Identity::Author => write!(f, "author"),
+            Identity::Committer => write!(f, "committer"),
+        }
+    }
+}
+
+/// All stats computed by [`analyze`]
+#[derive(Serialize, Deserialize)]
+pub struct RepositoryStats {
+    // Applied filters
+    pub author_filter: Option<String>,
+    pub excluded_authors: Vec<String>,
+    pub commit_limit: Option<usize>,
+    pub path_filter: Vec<String>,
+    /// The "<base>..<head>" range, if only the commits between two refs were analyzed
+    pub compared_range: Option<String>,
+    pub merges_excluded: bool,
+    pub empty_excluded: bool,
+    /// (sampled, all) commits to diff, if the changed lines stats are estimated from a sample
+    pub churn_sample: Option<(usize, usize)>,
+    /// Algorithm the changed lines stats were computed with
+    pub diff_algorithm: DiffAlgorithm,
+    pub first_parent_only: bool,
+    /// Whether the contributors are the authors or committers of the commits
+    pub contributor_identity: Identity,
+    /// Current time (seconds since the epoch) the stats were computed at, see [`AnalysisOptions::now`]
+    pub analyzed_at: i64,
+
+    // General stats
+    /// Branch (or other ref) that was analyzed, or the short commit id for a detached HEAD
+    pub analyzed_branch: Option<String>,
+    pub commit_count: usize,
+    pub merge_commits: usize,
+    /// Commits without changes, only computed for the extended overview
+    pub empty_commits: usize,
+    pub first_commit: u64,
+    pub last_commit: u64,
+    /// Commits in the last [`QUARTER_DAYS`] before the analysis and in the same span before that, for the trend
+    pub commits_last_quarter: usize,
+    pub commits_prior_quarter: usize,
+    /// Commits per contributor, which are fractional if commits are split between co-authors
+    pub contributors: HashMap<String, f64>,
+
+    // Extended stats
+    pub total_files_changes: usize,
+    /// Changes of binary files, which are part of the files changed but have no lines
+    pub binary_files_changed: usize,
+    pub total_lines_inserted: usize,
+    pub total_lines_removed: usize,
+    pub lines_per_author: HashMap<String, (usize, usize)>,
+    /// Mean and median seconds between consecutive commits, if there are at least two commits
+    pub average_commit_interval: Option<u64>,
+    pub median_commit_interval: Option<u64>,
+    /// Whether the stats were combined from multiple repositories, which leaves out the commit intervals
+    pub is_combined: bool,
+
+    // Language stats
+    /// (insertions, deletions) per file extension like ".rs", or "(none)"
+    pub lines_per_extension: HashMap<String, (usize, usize)>,
+
+    // Directory stats
+    /// (insertions, deletions) per top-level directory, or "(root)" for files in the root
+    pub lines_per_directory: HashMap<String, (usize, usize)>,
+
+    // Commit size stats
+    /// Commits by changed lines (insertions + deletions): 0, 1-10, 11-100, 101-1000 and more than 1000
+    pub commit_size_buckets: [usize; 5],
+
+    // Hotspot stats
+    /// Commits that changed the file, by its path (the new one for renamed files)
+    pub commits_per_file: HashMap<String, usize>,
+
+    // Coupling stats
+    /// Pairs of files changed together by the most commits, sorted descending by the commits
+    pub coupled_files: Vec<CoupledFiles>,
+
+    // Tag stats
+    pub tag_count: usize,
+    /// Name and commit time of the tag pointing to the most recent commit
+    pub latest_tag: Option<(String, u64)>,
+
+    // Branch stats
+    pub branches: Vec<BranchStats>,
+
+    // Largest files stats
+    /// Largest files in the tree of the analyzed ref, sorted descending by size
+    pub largest_files: Vec<FileSize>,
+
+    // Repository size stats
+    pub repository_size: RepositorySize,
+
+    // Contributor growth stats
+    /// Every month from the first to the last commit, in chronological order
+    pub contributor_growth: Vec<ContributorGrowth>,
+
+    // Author timeline stats
+    /// Times of the first and last commit per contributor
+    pub author_first_last: HashMap<String, (u64, u64)>,
+
+    // Contributor class stats
+    /// Commits that make a contributor a core one, regardless of their last commit
+    pub min_core_commits: usize,
+
+    // Checkerboard stats
+    pub commits_last_year: usize,
+    pub longest_commit_streak: usize,
+    pub current_commit_streak: usize,
+    pub max_commits_a_day: usize,
+    #[serde(with = "day_buffer")]
+    pub commits_per_day_last_year: [usize; DAYS_IN_GRAPH],
+
+    // All-time heatmap
+    /// Commits per day of each year, indexed by the day of the year (starting at 0 for January 1st)
+    #[serde(with = "yearly_day_buffers")]
+    pub commits_per_day_by_year: HashMap<i32, [usize; MAX_DAYS_IN_YEAR]>,
+
+    // Weekday stats
+    pub commits_per_weekday: [usize; 7],
+    /// Earliest and latest day with a commit (in days since the common era), both in the timezone of the commit
+    pub weekday_window: Option<(i32, i32)>,
+
+    // Hour stats
+    /// Commits per hour of the day, in the timezone the commit was made in
+    pub commits_per_hour: [usize; 24],
+
+    // Commit message stats
+    pub total_subject_length: usize,
+    /// Commits whose message has a line longer than [`MAX_MESSAGE_LINE_LENGTH`]
+    pub long_messages: usize,
+    pub conventional_commits: usize,
+    /// Commits created by `git revert`, whose subject starts with "Revert "
+    pub revert_commits: usize,
+    /// Commits whose subject starts with "fixup! " or "squash! ", which were meant to be squashed by a rebase
+    pub fixup_commits: usize,
+
+    // Signature stats
+    /// Commits with a GPG or SSH signature, whether it is valid or not
+    pub signed_commits: usize,
+
+    // Which of the optional stats were actually computed
+    pub has_extended_stats: bool,
+    pub has_commit_graph: bool,
+    pub has_all_time_heatmap: bool,
+    pub has_weekday_stats: bool,
+    pub has_hour_stats: bool,
+    pub has_message_stats: bool,
+    pub has_signature_stats: bool,
+    pub has_lang_stats: bool,
+    pub has_dir_stats: bool,
+    pub has_size_histogram: bool,
+    pub has_hotspots: bool,
+    pub has_coupling: bool,
+    pub has_tag_stats: bool,
+    pub has_branch_stats: bool,
+    pub has_growth_stats: bool,
+    pub has_author_timeline: bool,
+    pub has_contributor_classes: bool,
+    pub has_largest_files: bool,
+    pub has_repository_size: bool,
+}
+
+/// A local branch compared to HEAD
+#[derive(Serialize, Deserialize)]
+pub struct BranchStats {
+    pub name: String,
+    /// Commits on the branch that are not reachable from HEAD
+    pub ahead: usize,
+    /// Commits reachable from HEAD that are not on the branch
+    pub behind: usize,
+    pub last_commit: u64,
+}
+
+/// A file in the tree of a commit
+#[derive(Serialize, Deserialize)]
+pub struct FileSize {
+    pub path: String,
+    /// Size of the content in bytes
+    pub size: u64,
+}
+
+/// Two files that were changed by the same commits
+#[derive(Serialize, Deserialize)]
+pub struct CoupledFiles {
+    /// The path that sorts first
+    pub first: String,
+    pub second: String,
+    /// Commits that changed both files
+    pub commits: usize,
+}
+
+/// Objects in the object database and the space the repository takes up on disk
+#[derive(Serialize, Deserialize, Default)]
+pub struct RepositorySize {
+    pub commits: usize,
+    pub trees: usize,
+    pub blobs: usize,
+    pub tags: usize,
+    /// Size of the pack files in bytes
+    pub packed_size: u64,
+    /// Size of the whole .git directory (or the bare repository) in bytes
+    pub git_dir_size: u64,
+}
+
+/// A single analyzed commit, as passed to the callback of [`analyze_with_commits`]
+#[derive(Serialize, Clone)]
+pub struct CommitRecord {
+    pub id: String,
+    /// Name and email of the contributor, i.e. the committer instead if they are analyzed
+    pub author_name: String,
+    pub author_email: String,
+    /// Unix timestamp of the commit
+    pub time: i64,
+    pub parent_count: usize,
+    /// Changed lines compared to the first parent, only known with the extended stats
+    pub insertions: Option<usize>,
+    pub deletions: Option<usize>,
+}
+
+/// The contributors of a single month
+#[derive(Serialize, Deserialize)]
+pub struct ContributorGrowth {
+    pub year: i32,
+    pub month: u32,
+    /// Distinct contributors that committed in this month
+    pub active_contributors: usize,
+    /// Distinct contributors that committed in this or any earlier month
+    pub total_contributors: usize,
+}
+
+/// Serde only supports arrays of up to 32 elements, so the per-day buffer is (de)serialized as a sequence
+mod day_buffer {
+    use super::DAYS_IN_GRAPH;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        buffer: &[usize; DAYS_IN_GRAPH],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(buffer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<[usize; DAYS_IN_GRAPH], D::Error> {
+        let buffer = Vec::<usize>::deserialize(deserializer)?;
+        buffer
+            .try_into()
+            .map_err(|buffer: Vec<usize>| D::Error::invalid_length(buffer.len(), &"366 days"))
+    }
+}
+
+/// Same as [`day_buffer`], but for the per-day buffers of each year
+mod yearly_day_buffers {
+    use super::MAX_DAYS_IN_YEAR;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+    use std::collections::HashMap;
+
+    pub fn serialize<S: Serializer>(
+        buffers: &HashMap<i32, [usize; MAX_DAYS_IN_YEAR]>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(
+            buffers
+                .iter()
+                .map(|(year, buffer)| (year, buffer.as_slice())),
+        )
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<HashMap<i32, [usize; MAX_DAYS_IN_YEAR]>, D::Error> {
+        HashMap::<i32, Vec<usize>>::deserialize(deserializer)?
+            .into_iter()
+            .map(|(year, buffer)| {
+                let buffer = buffer.try_into().map_err(|buffer: Vec<usize>| {
+                    D::Error::invalid_length(buffer.len(), &"366 days")
+                })?;
+                Ok((year, buffer))
+            })
+            .collect()
+    }
+}
+
+impl RepositoryStats {
+    /// The local date the stats were computed at, which is the last day of the commit graph
+    pub fn today(&self) -> NaiveDate {
+        local_date(self.analyzed_at)
+    }
+
+    /// The day of the commit graph with the most commits (the most recent one on ties), if there are any
+    pub fn busiest_day(&self) -> Option<(NaiveDate, usize)> {
+        let (index, commits) = self
+            .commits_per_day_last_year
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, commits)| **commits)?;
+        if *commits == 0 {
+            return None;
+        }
+        let days_ago = (DAYS_IN_GRAPH - 1 - index) as i64;
+        Some((self.today() - chrono::Duration::days(days_ago), *commits))
+    }
+}
+
+/// Gets the date in the local timezone of the seconds since the epoch
+fn local_date(timestamp: i64) -> NaiveDate {
+    DT::from_timestamp(timestamp, 0)
+        .unwrap_or_default()
+        .with_timezone(&Local)
+        .date_naive()
+}
+
+/// Empty stats, with none of the optional stats being marked as computed
+impl Default for RepositoryStats {
+    fn default() -> Self {
+        RepositoryStats {
+            author_filter: None,
+            excluded_authors: Vec::new(),
+            commit_limit: None,
+            path_filter: Vec::new(),
+            compared_range: None,
+            merges_excluded: false,
+            empty_excluded: false,
+            churn_sample: None,
+            diff_algorithm: DiffAlgorithm::Myers,
+            first_parent_only: false,
+            contributor_identity: Identity::Author,
+            analyzed_at: 0,
+
+            analyzed_branch: None,
+            commit_count: 0,
+            merge_commits: 0,
+            empty_commits: 0,
+            commits_last_quarter: 0,
+            commits_prior_quarter: 0,
+            first_commit: 0,
+            last_commit: 0,
+            contributors: HashMap::new(),
+
+            total_files_changes: 0,
+            binary_files_changed: 0,
+            total_lines_inserted: 0,
+            total_lines_removed: 0,
+            lines_per_author: HashMap::new(),
+            average_commit_interval: None,
+            median_commit_interval: None,
+            is_combined: false,
+
+            lines_per_extension: HashMap::new(),
+            lines_per_directory: HashMap::new(),
+
+            commit_size_buckets: [0; 5],
+            commits_per_file: HashMap::new(),
+            coupled_files: Vec::new(),
+
+            tag_count: 0,
+            latest_tag: None,
+
+            branches: Vec::new(),
+            largest_files: Vec::new(),
+            repository_size: RepositorySize::default(),
+
+            contributor_growth: Vec::new(),
+            author_first_last: HashMap::new(),
+            min_core_commits: 0,
+
+            commits_last_year: 0,
+            longest_commit_streak: 0,
+            current_commit_streak: 0,
+            max_commits_a_day: 0,
+            commits_per_day_last_year: [0; DAYS_IN_GRAPH],
+
+            commits_per_day_by_year: HashMap::new(),
+
+            commits_per_weekday: [0; 7],
+            weekday_window: None,
+
+            commits_per_hour: [0; 24],
+
+            total_subject_length: 0,
+            long_messages: 0,
+            conventional_commits: 0,
+            revert_commits: 0,
+            fixup_commits: 0,
+
+            signed_commits: 0,
+
+            has_extended_stats: false,
+            has_commit_graph: false,
+            has_all_time_heatmap: false,
+            has_weekday_stats: false,
+            has_hour_stats: false,
+            has_message_stats: false,
+            has_signature_stats: false,
+            has_lang_stats: false,
+            has_dir_stats: false,
+            has_size_histogram: false,
+            has_hotspots: false,
+            has_coupling: false,
+            has_tag_stats: false,
+            has_branch_stats: false,
+            has_growth_stats: false,
+            has_author_timeline: false,
+            has_contributor_classes: false,
+            has_largest_files: false,
+            has_repository_size: false,
+        }
+    }
+}
+
+/// Errors that abort the analysis
+#[derive(Debug)]
+pub enum RepolyzerError {
+    /// No arguments were passed at all
+    NoArgs,
+    /// An argument could not be understood
+    BadArgs(String),
+    /// The provided path to the repository is not usable
+    BadPath(String),
+    /// The local repository could not be opened
+    RepoOpen,
+    /// The remote repository could not be cloned
+    RepoClone(String),
+    /// The requested ref could not be resolved
+    RefNotFound(String),
+    /// The requested revision range could not be parsed or resolved
+    BadRange(String),
+    /// The stats could not be written
+    Output(String),
+    /// The configuration file could not be read
+    BadConfig(String),
+}
+
+impl RepolyzerError {
+    /// The code the program exits with for this error
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            RepolyzerError::NoArgs
+            | RepolyzerError::BadArgs(_)
+            | RepolyzerError::BadPath(_)
+            | RepolyzerError::BadConfig(_) => EXIT_USAGE,
+            RepolyzerError::RepoOpen => EXIT_REPO_OPEN,
+            RepolyzerError::RepoClone(_) => EXIT_CLONE,
+            RepolyzerError::RefNotFound(_) | RepolyzerError::BadRange(_) => EXIT_REF_NOT_FOUND,
+            RepolyzerError::Output(_) => EXIT_OUTPUT,
+        }
+    }
+}
+
+impl fmt::Display for RepolyzerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RepolyzerError::NoArgs => write!(f, "No arguments were passed!"),
+            RepolyzerError::BadArgs(message) => write!(f, "{}", message),
+            RepolyzerError::BadPath(message) => write!(f, "{}", message),
+            RepolyzerError::RepoOpen => write!(f, "Could not open the local repository!"),
+            RepolyzerError::RepoClone(message) => write!(f, "{}", message),
+            RepolyzerError::RefNotFound(reference) => {
+                write!(f, "Could not resolve the ref '{}'!", reference)
+            }
+            RepolyzerError::BadRange(message) => write!(f, "{}", message),
+            RepolyzerError::Output(message) => write!(f, "{}", message),
+            RepolyzerError::BadConfig(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for RepolyzerError {}
+
+/// Changes introduced by a single commit compared to its first parent
+#[derive(Default)]
+struct CommitDiffStats {
+    files_changed: usize,
+    // Changed files without lines, which are part of files_changed
+    binary_files_changed: usize,
+    insertions: usize,
+    deletions: usize,
+    // (insertions, deletions) per file extension, only computed for the language stats
+    lines_per_extension: HashMap<String, (usize, usize)>,
+    // (insertions, deletions) per top-level directory, only computed for the directory stats
+    lines_per_directory: HashMap<String, (usize, usize)>,
+    // Paths of the changed files, only collected for the hotspots
+    changed_paths: Vec<String>,
+    // Every two of the changed files, only collected for the coupling
+    file_pairs: Vec<(String, String)>,
+}
+
+/// Accumulated changes of all commits that were diffed for the extended overview
+#[derive(Default)]
+struct DiffTotals {
+    files_changed: usize,
+    binary_files_changed: usize,
+    insertions: usize,
+    deletions: usize,
+    // (insertions, deletions) per author
+    lines_per_author: HashMap<String, (usize, usize)>,
+    // (insertions, deletions) per file extension
+    lines_per_extension: HashMap<String, (usize, usize)>,
+    // (insertions, deletions) per top-level directory
+    lines_per_directory: HashMap<String, (usize, usize)>,
+    commit_size_buckets: [usize; 5],
+    commits_per_file: HashMap<String, usize>,
+    commits_per_file_pair: HashMap<(String, String), usize>,
+}
+
+impl DiffTotals {
+    /// Adds the changes of a single commit made by the given author
+    fn add(mut self, author: &str, diff: CommitDiffStats) -> Self {
+        self.files_changed += diff.files_changed;
+        self.binary_files_changed += diff.binary_files_changed;
+        self.insertions += diff.insertions;
+        self.deletions += diff.deletions;
+        add_lines(
+            &mut self.lines_per_author,
+            author.to_string(),
+            (diff.insertions, diff.deletions),
+        );
+        for (extension, lines) in diff.lines_per_extension {
+            add_lines(&mut self.lines_per_extension, extension, lines);
+        }
+        for (directory, lines) in diff.lines_per_directory {
+            add_lines(&mut self.lines_per_directory, directory, lines);
+        }
+        self.commit_size_buckets[commit_size_bucket(diff.insertions + diff.deletions)] += 1;
+        for path in diff.changed_paths {
+            *self.commits_per_file.entry(path).or_insert(0) += 1;
+        }
+        for pair in diff.file_pairs {
+            *self.commits_per_file_pair.entry(pair).or_insert(0) += 1;
+        }
+        self
+    }
+
+    /// Combines the totals of two disjoint sets of commits
+    fn merge(mut self, other: DiffTotals) -> Self {
+        self.files_changed += other.files_changed;
+        self.binary_files_changed += other.binary_files_changed;
+        self.insertions += other.insertions;
+        self.deletions += other.deletions;
+        for (author, lines) in other.lines_per_author {
+            add_lines(&mut self.lines_per_author, author, lines);
+        }
+        for (extension, lines) in other.lines_per_extension {
+            add_lines(&mut self.lines_per_extension, extension, lines);
+        }
+        for (directory, lines) in other.lines_per_directory {
+            add_lines(&mut self.lines_per_directory, directory, lines);
+        }
+        for (bucket, commits) in other.commit_size_buckets.iter().enumerate() {
+            self.commit_size_buckets[bucket] += commits;
+        }
+        for (path, commits) in other.commits_per_file {
+            *self.commits_per_file.entry(path).or_insert(0) += commits;
+        }
+        for (pair, commits) in other.commits_per_file_pair {
+            *self.commits_per_file_pair.entry(pair).or_insert(0) += commits;
+        }
+        self
+    }
+}
+
+impl DiffTotals {
+    /// Extrapolates the totals of a sample onto all commits
+    fn scale(mut self, factor: f64) -> Self {
+        let scale = |value: &mut usize| *value = (*value as f64 * factor).round() as usize;
+        let scale_lines = |lines: &mut (usize, usize)| {
+            scale(&mut lines.0);
+            scale(&mut lines.1);
+        };
+        scale(&mut self.files_changed);
+        scale(&mut self.binary_files_changed);
+        scale(&mut self.insertions);
+        scale(&mut self.deletions);
+        self.lines_per_author.values_mut().for_each(scale_lines);
+        self.lines_per_extension.values_mut().for_each(scale_lines);
+        self.lines_per_directory.values_mut().for_each(scale_lines);
+        self.commit_size_buckets.iter_mut().for_each(scale);
+        self.commits_per_file.values_mut().for_each(scale);
+        self.commits_per_file_pair.values_mut().for_each(scale);
+        self
+    }
+}
+
+/// Whether the commit is part of a sample with the given rate.
+/// The commit id is a hash and therefore already evenly distributed, so every run picks the same commits.
+fn is_sampled(commit_id: Oid, rate: f64) -> bool {
+    let bytes: [u8; 8] = commit_id.as_bytes()[..8].try_into().unwrap();
+    (u64::from_be_bytes(bytes) as f64) < rate * u64::MAX as f64
+}
+
+/// Gets the bucket of [`RepositoryStats::commit_size_buckets`] for a commit with the given changed lines
+fn commit_size_bucket(changed_lines: usize) -> usize {
+    match changed_lines {
+        0 => 0,
+        1..=10 => 1,
+        11..=100 => 2,
+        101..=1000 => 3,
+        _ => 4,
+    }
+}
+
+/// Adds the (insertions, deletions) to the entry of the key
+fn add_lines(map: &mut HashMap<String, (usize, usize)>, key: String, lines: (usize, usize)) {
+    let entry = map.entry(key).or_insert((0, 0));
+    entry.0 += lines.0;
+    entry.1 += lines.1;
+}
+
+/// Simple progress counter on stderr, which is only shown on terminals
+struct Progress {
+    enabled: bool,
+}
+
+impl Progress {
+    /// How many commits are processed between two updates of the counter
+    const UPDATE_INTERVAL: usize = 100;
+
+    fn new(enabled: bool) -> Self {
+        Progress {
+            enabled: enabled && std::io::stderr().is_terminal(),
+        }
+    }
+
+    /// Shows the amount of processed commits for the current stage
+    fn tick(&self, stage: &str, processed: usize, total: Option<usize>) {
+        if !self.enabled || !processed.is_multiple_of(Self::UPDATE_INTERVAL) {
+            return;
+        }
+        match total {
+            Some(total) => eprint!("\r{} {}/{} commits", stage, processed, total),
+            None => eprint!("\r{} {} commits", stage, processed),
+        }
+    }
+
+    /// Shows the overview stats gathered so far, while walking the commits
+    fn live_stats(&self, walked: usize, stats: &RepositoryStats) {
+        if !self.enabled || !walked.is_multiple_of(Self::UPDATE_INTERVAL) {
+            return;
+        }
+        let last_commit = DT::from_timestamp(stats.last_commit as i64, 0)
+            .filter(|_| stats.commit_count > 0)
+            .map(|time| time.with_timezone(&Local).format("%Y-%m-%d").to_string())
+            .unwrap_or_else(|| "-".to_string());
+        eprint!(
+            "\r\x1b[2KWalking {} commits: {} commits by {} contributors, last commit {}",
+            walked,
+            stats.commit_count,
+            stats.contributors.len(),
+            last_commit
+        );
+    }
+
+    /// Removes the counter again, so that it doesn't mix with the actual output
+    fn clear(&self) {
+        if self.enabled {
+            eprint!("\r\x1b[2K");
+        }
+    }
+}
+
+/// Analyzes the history of the repository, computing the optional stats enabled in the options
+pub fn analyze(
+    repository: &Repository,
+    opts: &AnalysisOptions,
+) -> Result<RepositoryStats, RepolyzerError> {
+    analyze_with_commits(repository, opts, None)
+}
+
+/// Like [`analyze`], but additionally passes every analyzed commit to `on_commit` as soon as it is known.
+/// With the extended stats, the commits are only passed on once they are diffed, which happens in parallel
+/// and therefore in no particular order.
+pub fn analyze_with_commits(
+    repository: &Repository,
+    opts: &AnalysisOptions,
+    on_commit: Option<&(dyn Fn(&CommitRecord) + Sync)>,
+) -> Result<RepositoryStats, RepolyzerError> {
+    let mut path_options = path_diff_options(&opts.paths);
+
+    let today = local_date(opts.now());
+
+    let mut ignored_paths = GlobSetBuilder::new();
+    for pattern in &opts.ignore_paths {
+        let glob = Glob::new(pattern).map_err(|e| {
+            RepolyzerError::BadArgs(format!("Invalid glob '{}': {}", pattern, e.kind()))
+        })?;
+        ignored_paths.add(glob);
+    }
+    let ignored_paths = ignored_paths
+        .build()
+        .map_err(|e| RepolyzerError::BadArgs(format!("Invalid globs: {}", e)))?;
+
+    let mut stats = RepositoryStats {
+        author_filter: opts.author.clone(),
+        excluded_authors: opts.exclude_authors.clone(),
+        commit_limit: opts.limit,
+        path_filter: opts.paths.clone(),
+        compared_range: opts.compare.clone().or_else(|| opts.range.clone()),
+        merges_excluded: opts.no_merges,
+        empty_excluded: opts.no_empty,
+        diff_algorithm: opts.diff_algorithm,
+        first_parent_only: opts.first_parent,
+        contributor_identity: opts.identity,
+        analyzed_at: opts.now(),
+        min_core_commits: opts.contributor_classes.unwrap_or(0),
+
+        has_extended_stats: opts.extended_stats,
+        has_commit_graph: opts.commit_graph,
+        has_all_time_heatmap: opts.all_time_heatmap,
+        has_weekday_stats: opts.weekday_stats,
+        has_hour_stats: opts.hour_stats,
+        has_message_stats: opts.message_stats,
+        has_signature_stats: opts.signature_stats,
+        has_lang_stats: opts.lang_stats,
+        has_dir_stats: opts.dir_stats,
+        has_size_histogram: opts.size_histogram,
+        has_hotspots: opts.hotspots,
+        has_coupling: opts.coupling.is_some(),
+        has_tag_stats: opts.tag_stats,
+        has_branch_stats: opts.branch_stats,
+        has_growth_stats: opts.growth_stats,
+        has_author_timeline: opts.author_timeline,
+        has_contributor_classes: opts.contributor_classes.is_some(),
+        has_largest_files: opts.largest_files.is_some(),
+        has_repository_size: opts.repo_size,
+        ..RepositoryStats::default()
+    };
+
+    // Resolve the requested ref before walking, so that a typo doesn't lead to a half-done analysis
+    let mut hidden_commits = Vec::new();
+    let start_commits = if let Some(range) = &opts.compare {
+        let (base, head) = resolve_range(repository, range)?;
+        hidden_commits.push(base);
+        vec![head]
+    } else if let Some(spec) = &opts.range {
+        let start_commits;
+        (start_commits, hidden_commits) = resolve_revisions(repository, spec)?;
+        start_commits
+    } else if let Some(reference) = &opts.reference {
+        let (object, git_ref) = repository
+            .revparse_ext(reference)
+            .map_err(|_| RepolyzerError::RefNotFound(reference.to_string()))?;
+        let commit = object
+            .peel_to_commit()
+            .map_err(|_| RepolyzerError::RefNotFound(reference.to_string()))?;
+        stats.analyzed_branch = Some(ref_name(git_ref.as_ref(), &commit));
+        vec![commit.id()]
+    } else {
+        Vec::new()
+    };
+
+    if opts.tag_stats {
+        (stats.tag_count, stats.latest_tag) = tag_stats(repository);
+    }
+
+    // Commits are walked oldest-first by commit time, instead of relying on the default order of git2.
+    // Only with a commit limit the walk starts at the newest commit, so that the most recent ones are kept.
+    let sorting = match opts.limit {
+        Some(_) => Sort::TIME,
+        None => Sort::TIME | Sort::REVERSE,
+    };
+    let mut revwalk = repository.revwalk().expect("Failed to get 'revwalk'");
+    revwalk
+        .set_sorting(sorting)
+        .expect("Failed to set revwalk sorting");
+    // As only the walked commits count, the limit and streaks also only see the mainline
+    if opts.first_parent {
+        revwalk
+            .simplify_first_parent()
+            .expect("Failed to simplify the revwalk");
+    }
+    for hidden_commit in hidden_commits {
+        revwalk.hide(hidden_commit).expect("Failed to hide ref!");
+    }
+    if !start_commits.is_empty() {
+        for start_commit in &start_commits {
+            revwalk.push(*start_commit).expect("Failed to push ref!");
+        }
+    } else {
+        // HEAD does not point to a commit yet, so there is nothing to analyze
+        match repository.head() {
+            Ok(head) => {
+                if let Ok(commit) = head.peel_to_commit() {
+                    stats.analyzed_branch = Some(ref_name(Some(&head), &commit));
+                }
+            }
+            Err(e) if matches!(e.code(), ErrorCode::UnbornBranch | ErrorCode::NotFound) => {
+                return Ok(stats);
+            }
+            Err(_) => {}
+        }
+        revwalk.push_head().expect("Failed to push HEAD!");
+    }
+
+    if opts.branch_stats {
+        stats.branches = branch_stats(repository);
+    }
+    if let Some(amount) = opts.largest_files {
+        stats.largest_files = largest_files(repository, start_commits.first().copied(), amount);
+    }
+    if opts.repo_size {
+        stats.repository_size = repository_size(repository);
+    }
+
+    let mut extended_commits: Vec<(Oid, String, Option<CommitRecord>)> = Vec::new();
+    // Commits that would be diffed without sampling
+    let mut diffable_commits = 0;
+    let mut commit_times: Vec<u64> = Vec::new();
+    // Calendar days of the last year with at least one commit, for the streaks
+    let mut active_days: BTreeSet<NaiveDate> = BTreeSet::new();
+    let mut authors_per_month: BTreeMap<(i32, u32), HashSet<String>> = BTreeMap::new();
+
+    // Authors are resolved through the .mailmap (if any), so that aliases count as one contributor
+    let mailmap = repository.mailmap().ok();
+
+    // Loop over all commit_ids with the help of revwalk
+    let progress = Progress::new(opts.progress);
+    for (walked, commit_id) in revwalk.enumerate() {
+        if opts.limit.is_some_and(|limit| stats.commit_count >= limit) {
+            break;
+        }
+        if opts.live_stats {
+            progress.live_stats(walked + 1, &stats);
+        } else {
+            progress.tick("Walking", walked + 1, None);
+        }
+        let commit_id = commit_id.expect("Failed to get commit ID");
+        let commit = repository
+            .find_commit(commit_id)
+            .expect("Could not find commit");
+
+        // Skip commits outside of the requested date range
+        let commit_seconds = commit.time().seconds();
+        if opts.since.is_some_and(|since| commit_seconds < since)
+            || opts.until.is_some_and(|until| commit_seconds > until)
+        {
+            continue;
+        }
+
+        if opts.no_merges && commit.parent_count() > 1 {
+            continue;
+        }
+
+        let is_empty = (opts.extended_stats || opts.no_empty) && is_empty_commit(&commit);
+        if opts.no_empty && is_empty {
+            continue;
+        }
+
+        // Skip commits that don't change anything in the requested paths
+        if !opts.paths.is_empty() && !touches_paths(repository, &commit, &mut path_options) {
+            continue;
+        }
+
+        // The contributor of the commit, which is its author unless the committers are analyzed
+        let author = match (&mailmap, opts.identity) {
+            (Some(mailmap), Identity::Author) => commit
+                .author_with_mailmap(mailmap)
+                .unwrap_or_else(|_| commit.author()),
+            (Some(mailmap), Identity::Committer) => commit
+                .committer_with_mailmap(mailmap)
+                .unwrap_or_else(|_| commit.committer()),
+            (None, Identity::Author) => commit.author(),
+            (None, Identity::Committer) => commit.committer(),
+        };
+
+        // Skip commits of other authors, if filtered by author
+        if let Some(pattern) = &opts.author {
+            if !author_matches(&author, pattern) {
+                continue;
+            }
+        }
+        if opts
+            .exclude_authors
+            .iter()
+            .any(|pattern| author_matches(&author, pattern))
+        {
+            continue;
+        }
+
+        // A commit was found
+        stats.commit_count += 1;
+        if commit.parent_count() > 1 {
+            stats.merge_commits += 1;
+        }
+        if is_empty {
+            stats.empty_commits += 1;
+        }
+
+        // Merges only repeat the changes of the merged branches in their diff to the first parent
+        let diffs_commit = opts.diffs_commits()
+            && (commit.parent_count() <= 1 || opts.churn_merges == ChurnMerges::FirstParent);
+        if diffs_commit {
+            diffable_commits += 1;
+        }
+        let diffs_commit = diffs_commit
+            && opts
+                .sample_rate
+                .is_none_or(|rate| is_sampled(commit_id, rate));
+
+        let mut record = on_commit.map(|_| CommitRecord {
+            id: commit_id.to_string(),
+            author_name: author_name(&author),
+            author_email: lossy(author.email_bytes()).into_owned(),
+            time: commit_seconds,
+            parent_count: commit.parent_count(),
+            insertions: None,
+            deletions: None,
+        });
+        // Without the extended stats or a diff, there is nothing more to wait for
+        if !opts.extended_stats || !diffs_commit {
+            if let (Some(on_commit), Some(record)) = (on_commit, record.take()) {
+                on_commit(&record);
+            }
+        }
+
+        // Add contributor to hashmap and update commit amount
+        let author = normalize_author(author_name(&author), opts);
+        let mut credited = vec![author.clone()];
+        // Trailers only name further authors, not committers
+        if opts.coauthors != CoauthorMode::Ignore && opts.identity == Identity::Author {
+            for (name, email) in co_authors(&lossy(commit.message_bytes())) {
+                let Ok(co_author) = git2::Signature::now(&name, &email) else {
+                    continue;
+                };
+                let co_author = match &mailmap {
+                    Some(mailmap) => mailmap.resolve_signature(&co_author).unwrap_or(co_author),
+                    None => co_author,
+                };
+                if opts
+                    .exclude_authors
+                    .iter()
+                    .any(|pattern| author_matches(&co_author, pattern))
+                {
+                    continue;
+                }
+                let name = normalize_author(author_name(&co_author), opts);
+                if !credited.contains(&name) {
+                    credited.push(name);
+                }
+            }
+        }
+        let credit = match opts.coauthors {
+            CoauthorMode::Split => 1.0 / credited.len() as f64,
+            _ => 1.0,
+        };
+        if opts.growth_stats {
+            // Gather the contributors per month
+            let time = commit_local_time(commit.time());
+            authors_per_month
+                .entry((time.year(), time.month()))
+                .or_default()
+                .extend(credited.iter().cloned());
+        }
+        if opts.author_timeline || opts.contributor_classes.is_some() {
+            let time = commit_seconds as u64;
+            for name in &credited {
+                let (first, last) = stats
+                    .author_first_last
+                    .entry(name.clone())
+                    .or_insert((time, time));
+                *first = (*first).min(time);
+                *last = (*last).max(time);
+            }
+        }
+        for name in credited {
+            *stats.contributors.entry(name).or_insert(0.0) += credit;
+        }
+
+        let commit_time = commit_seconds as u64;
+        if stats.last_commit < commit_time {
+            stats.last_commit = commit_time;
+        }
+        if stats.commit_count == 1 || commit_time < stats.first_commit {
+            stats.first_commit = commit_time;
+        }
+        let quarters_ago = (stats.analyzed_at - commit_seconds).div_euclid(QUARTER_DAYS * 86_400);
+        match quarters_ago {
+            0 => stats.commits_last_quarter += 1,
+            1 => stats.commits_prior_quarter += 1,
+            _ => {}
+        }
+
+        // Remember commit for the diff based stats, as the diffs are computed in parallel afterwards
+        if diffs_commit {
+            extended_commits.push((commit_id, author, record));
+        }
+        if opts.extended_stats {
+            commit_times.push(commit_time);
+        }
+
+        if opts.commit_graph {
+            // Gather commits per day
+            let commit_date = DT::from_timestamp(commit_time as i64, 0)
+                .unwrap()
+                .with_timezone(&Local)
+                .date_naive();
+            if let Some(day_index) = day_index(commit_date, today) {
+                // Commit was made in the last year
+                stats.commits_per_day_last_year[day_index] += 1;
+                active_days.insert(commit_date);
+            }
+        }
+
+        if opts.all_time_heatmap {
+            // Gather commits per day of each year
+            let commit_date = DT::from_timestamp(commit_time as i64, 0)
+                .unwrap()
+                .with_timezone(&Local)
+                .date_naive();
+            let buffer = stats
+                .commits_per_day_by_year
+                .entry(commit_date.year())
+                .or_insert([0; MAX_DAYS_IN_YEAR]);
+            buffer[commit_date.ordinal0() as usize] += 1;
+        }
+
+        if opts.weekday_stats {
+            // Gather commits per weekday
+            let date = commit_local_time(commit.time()).date_naive();
+            stats.commits_per_weekday[date.weekday().num_days_from_monday() as usize] += 1;
+            // The occurrences of the weekdays are counted on the same days as the commits
+            let day = date.num_days_from_ce();
+            let window = stats.weekday_window.get_or_insert((day, day));
+            *window = (window.0.min(day), window.1.max(day));
+        }
+
+        if opts.hour_stats {
+            // Gather commits per hour of the day
+            let hour = commit_local_time(commit.time()).hour();
+            stats.commits_per_hour[hour as usize] += 1;
+        }
+
+        if opts.message_stats {
+            let subject = lossy(commit.summary_bytes().unwrap_or_default());
+            let subject = subject.as_ref();
+            stats.total_subject_length += subject.chars().count();
+            if is_conventional_commit(subject) {
+                stats.conventional_commits += 1;
+            }
+            // The prefixes are generated by git, so they are matched exactly
+            if subject.starts_with("Revert ") {
+                stats.revert_commits += 1;
+            }
+            if subject.starts_with("fixup! ") || subject.starts_with("squash! ") {
+                stats.fixup_commits += 1;
+            }
+            let message = lossy(commit.message_bytes());
+            if message
+                .lines()
+                .any(|line| line.chars().count() > MAX_MESSAGE_LINE_LENGTH)
+            {
+                stats.long_messages += 1;
+            }
+        }
+
+        // Unsigned commits have no signature to extract, which is not an error here
+        if opts.signature_stats && repository.extract_signature(&commit_id, None).is_ok() {
+            stats.signed_commits += 1;
+        }
+    }
+
+    if opts.diffs_commits() {
+        // Every thread needs its own repository handle, as git2 objects can't be shared across threads
+        let repository_path = repository.path();
+        let diffed = AtomicUsize::new(0);
+        let totals = extended_commits
+            .par_iter()
+            .map_init(
+                || Repository::open(repository_path).expect("Failed to open repository"),
+                |repository, (commit_id, author, record)| {
+                    let diff = diff_commit(repository, *commit_id, opts, &ignored_paths);
+                    if let (Some(on_commit), Some(record)) = (on_commit, record) {
+                        on_commit(&CommitRecord {
+                            insertions: Some(diff.insertions),
+                            deletions: Some(diff.deletions),
+                            ..record.clone()
+                        });
+                    }
+                    let processed = diffed.fetch_add(1, Ordering::Relaxed) + 1;
+                    progress.tick("Diffing", processed, Some(extended_commits.len()));
+                    (author, diff)
+                },
+            )
+            .fold(DiffTotals::default, |totals, (author, diff)| {
+                totals.add(author, diff)
+            })
+            .reduce(DiffTotals::default, DiffTotals::merge);
+        let totals = if opts.sample_rate.is_some() {
+            stats.churn_sample = Some((extended_commits.len(), diffable_commits));
+            totals.scale(diffable_commits as f64 / extended_commits.len().max(1) as f64)
+        } else {
+            totals
+        };
+
+        stats.total_files_changes = totals.files_changed;
+        stats.binary_files_changed = totals.binary_files_changed;
+        stats.total_lines_inserted = totals.insertions;
+        stats.total_lines_removed = totals.deletions;
+        stats.lines_per_author = totals.lines_per_author;
+        stats.lines_per_extension = totals.lines_per_extension;
+        stats.lines_per_directory = totals.lines_per_directory;
+        stats.commit_size_buckets = totals.commit_size_buckets;
+        stats.commits_per_file = totals.commits_per_file;
+        if let Some(amount) = opts.coupling {
+            stats.coupled_files = top_coupled_files(totals.commits_per_file_pair, amount);
+        }
+    }
+    progress.clear();
+
+    if opts.growth_stats {
+        stats.contributor_growth = contributor_growth(authors_per_month);
+    }
+
+    if opts.extended_stats {
+        (stats.average_commit_interval, stats.median_commit_interval) =
+            commit_intervals(commit_times);
+    }
+
+    if opts.commit_graph {
+        // Calculate max commits a day
+        stats.max_commits_a_day = *stats.commits_per_day_last_year.iter().max().unwrap();
+
+        // Calculate commits in the last year
+        stats.commits_last_year = stats.commits_per_day_last_year.iter().sum();
+
+        // Calculate streaks
+        (stats.longest_commit_streak, stats.current_commit_streak) =
+            compute_streaks(&active_days, today);
+    }
+
+    Ok(stats)
+}
+
+/// Compares all local branches to HEAD, sorted descending by their last commit
+fn branch_stats(repository: &Repository) -> Vec<BranchStats> {
+    let Ok(head) = repository.head().and_then(|head| head.peel_to_commit()) else {
+        // Without a commit at HEAD, there is nothing to compare to
+        return Vec::new();
+    };
+
+    let mut branches: Vec<BranchStats> = repository
+        .branches(Some(BranchType::Local))
+        .expect("Failed to get branches")
+        .flatten()
+        .filter_map(|(branch, _)| {
+            let name = lossy(branch.name_bytes().ok()?).into_owned();
+            let commit = branch.get().peel_to_commit().ok()?;
+            let (ahead, behind) = repository.graph_ahead_behind(commit.id(), head.id()).ok()?;
+            Some(BranchStats {
+                name,
+                ahead,
+                behind,
+                last_commit: commit.time().seconds() as u64,
+            })
+        })
+        .collect();
+    branches.sort_by(|a, b| b.last_commit.cmp(&a.last_commit).then(a.name.cmp(&b.name)));
+    branches
+}
+
+/// Collects the largest files in the tree of the commit (or HEAD), sorted descending by size.
+/// Only the current tree is walked, files that were removed from it or shrunk are not taken into account.
+fn largest_files(repository: &Repository, commit: Option<Oid>, amount: usize) -> Vec<FileSize> {
+    let commit = match commit {
+        Some(commit) => repository.find_commit(commit),
+        None => repository.head().and_then(|head| head.peel_to_commit()),
+    };
+    let (Ok(tree), Ok(odb)) = (commit.and_then(|commit| commit.tree()), repository.odb()) else {
+        return Vec::new();
+    };
+
+    let mut files = Vec::new();
+    tree.walk(TreeWalkMode::PreOrder, |directory, entry| {
+        // Submodules (commits) and directories have no size of their own
+        if entry.kind() == Some(ObjectType::Blob) {
+            // Reading only the header avoids loading the whole content
+            if let Ok((size, _)) = odb.read_header(entry.id()) {
+                files.push(FileSize {
+                    path: format!("{}{}", directory, lossy(entry.name_bytes())),
+                    size: size as u64,
+                });
+            }
+        }
+        TreeWalkResult::Ok
+    })
+    .expect("Failed to walk the tree");
+    files.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.path.cmp(&b.path)));
+    files.truncate(amount);
+    files
+}
+
+/// Counts the objects of the repository by their type and measures its size on disk.
+/// All objects of the object database are counted, not only the ones reachable from the analyzed ref.
+fn repository_size(repository: &Repository) -> RepositorySize {
+    let mut size = RepositorySize::default();
+    if let Ok(odb) = repository.odb() {
+        // Reading only the header avoids loading the whole content
+        let _ = odb.foreach(|id| {
+            match odb.read_header(*id).map(|(_, kind)| kind) {
+                Ok(ObjectType::Commit) => size.commits += 1,
+                Ok(ObjectType::Tree) => size.trees += 1,
+                Ok(ObjectType::Blob) => size.blobs += 1,
+                Ok(ObjectType::Tag) => size.tags += 1,
+                _ => {}
+            }
+            true
+        });
+    }
+    size.packed_size = directory_size(&repository.path().join("objects").join("pack"));
+    size.git_dir_size = directory_size(repository.path());
+    size
+}
+
+/// Sums up the sizes of all files in the directory and its subdirectories, without following symlinks
+fn directory_size(path: &std::path::Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.metadata() {
+            Ok(metadata) if metadata.is_dir() => directory_size(&entry.path()),
+            Ok(metadata) => metadata.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+/// Resolves a "<base>..<head>" range into the commits of base and head
+pub fn resolve_range(repository: &Repository, range: &str) -> Result<(Oid, Oid), RepolyzerError> {
+    let not_found = || RepolyzerError::RefNotFound(range.to_string());
+    let (base, head) = range.split_once("..").ok_or_else(not_found)?;
+    if base.is_empty() || head.is_empty() || head.starts_with('.') {
+        return Err(not_found());
+    }
+    let resolve = |name: &str| {
+        repository
+            .revparse_single(name)
+            .and_then(|object| object.peel_to_commit())
+            .map(|commit| commit.id())
+            .map_err(|_| RepolyzerError::RefNotFound(name.to_string()))
+    };
+    Ok((resolve(base)?, resolve(head)?))
+}
+
+/// Resolves a gitrevisions spec into the commits whose history is walked and the ones whose history is hidden:
+/// "<rev>" walks everything reachable from rev, "<a>..<b>" hides the history of a from the one of b
+/// and "<a>...<b>" walks the commits reachable from either a or b, but not from both.
+pub fn resolve_revisions(
+    repository: &Repository,
+    spec: &str,
+) -> Result<(Vec<Oid>, Vec<Oid>), RepolyzerError> {
+    let bad_range = |e: git2::Error| {
+        RepolyzerError::BadRange(format!(
+            "Could not resolve the range '{}': {}",
+            spec,
+            e.message()
+        ))
+    };
+    let revspec = repository.revparse(spec).map_err(bad_range)?;
+    let commit = |object: Option<&git2::Object>| match object {
+        Some(object) => object
+            .peel_to_commit()
+            .map(|commit| commit.id())
+            .map_err(bad_range),
+        None => Err(bad_range(git2::Error::from_str("missing revision"))),
+    };
+
+    if revspec.mode().contains(git2::RevparseMode::SINGLE) {
+        return Ok((vec![commit(revspec.from())?], Vec::new()));
+    }
+    let (from, to) = (commit(revspec.from())?, commit(revspec.to())?);
+    if revspec.mode().contains(git2::RevparseMode::MERGE_BASE) {
+        // Without a common history, nothing is shared and therefore nothing hidden
+        let hidden = repository.merge_base(from, to).into_iter().collect();
+        // The right side comes first, as it is what e.g. the largest files are listed for
+        return Ok((vec![to, from], hidden));
+    }
+    Ok((vec![to], vec![from]))
+}
+
+/// Names the analyzed ref by its short name (e.g. "main"), falling back to the short commit id if
+/// it isn't a branch or tag (e.g. a detached HEAD or a plain commit id)
+fn ref_name(reference: Option<&git2::Reference>, commit: &git2::Commit) -> String {
+    let name = reference
+        .filter(|reference| reference.is_branch() || reference.is_remote() || reference.is_tag())
+        .and_then(|reference| reference.shorthand());
+    match name {
+        Some(name) => name.to_string(),
+        None => commit
+            .as_object()
+            .short_id()
+            .ok()
+            .and_then(|id| id.as_str().map(str::to_string))
+            .unwrap_or_else(|| commit.id().to_string()),
+    }
+}
+
+/// Counts the tags and finds the one pointing to the most recent commit
+fn tag_stats(repository: &Repository) -> (usize, Option<(String, u64)>) {
+    let tag_names = repository.tag_names(None).expect("Failed to get tags");
+    let mut latest_tag: Option<(String, u64)> = None;
+    for name in tag_names.iter().flatten() {
+        let Ok(reference) = repository.find_reference(&format!("refs/tags/{}", name)) else {
+            continue;
+        };
+        let Some(target) = reference.target() else {
+            continue;
+        };
+        // Annotated tags are objects of their own, while lightweight tags point to the commit directly
+        let commit = match repository.find_tag(target) {
+            Ok(tag) => tag.target().and_then(|object| object.peel_to_commit()),
+            Err(_) => repository.find_commit(target),
+        };
+        // Tags may also point to trees or blobs, which have no date
+        let Ok(commit) = commit else {
+            continue;
+        };
+        let commit_time = commit.time().seconds() as u64;
+        if latest_tag
+            .as_ref()
+            .is_none_or(|(_, latest_time)| commit_time > *latest_time)
+        {
+            latest_tag = Some((name.to_string(), commit_time));
+        }
+    }
+    (tag_names.len(), latest_tag)
+}
+
+/// Computes the changes of a commit compared to its first parent.
+/// For the language and directory stats, the changed lines are also bucketed by file extension
+/// and top-level directory. Files matching the ignored paths are left out entirely.
+fn diff_commit(
+    repository: &Repository,
+    commit_id: Oid,
+    opts: &AnalysisOptions,
+    ignored_paths: &GlobSet,
+) -> CommitDiffStats {
+    let commit = repository
+        .find_commit(commit_id)
+        .expect("Could not find commit");
+    let parent = commit.parent(0);
+    if parent.is_err() {
+        // This is the first commit, so there is no parent
+        return CommitDiffStats::default();
+    }
+    let mut diff_options = path_diff_options(&opts.paths);
+    diff_options
+        .patience(opts.diff_algorithm == DiffAlgorithm::Patience)
+        .minimal(opts.diff_algorithm == DiffAlgorithm::Minimal)
+        .ignore_whitespace(opts.ignore_whitespace);
+    let diff = repository
+        .diff_tree_to_tree(
+            Some(&parent.unwrap().tree().unwrap()),
+            Some(&commit.tree().unwrap()),
+            Some(&mut diff_options),
+        )
+        .expect("Failed to get diff");
+
+    // Without the need to look at the single files (or lines), the stats of the whole diff suffice
+    if !opts.lang_stats && !opts.dir_stats && !opts.ignore_blank_lines && ignored_paths.is_empty() {
+        let diff_stats = diff.stats().expect("Failed to get stats");
+        let stats = CommitDiffStats {
+            files_changed: diff.deltas().count(),
+            // Whether a file is binary is known once the stats were computed
+            binary_files_changed: diff
+                .deltas()
+                .filter(|delta| delta.flags().is_binary())
+                .count(),
+            insertions: diff_stats.insertions(),
+            deletions: diff_stats.deletions(),
+            changed_paths: if opts.hotspots || opts.coupling.is_some() {
+                diff.deltas()
+                    .filter_map(|delta| delta_path(&delta))
+                    .collect()
+            } else {
+                Vec::new()
+            },
+            ..CommitDiffStats::default()
+        };
+        return pair_changed_paths(stats, opts);
+    }
+
+    let mut stats = CommitDiffStats::default();
+    for (index, delta) in diff.deltas().enumerate() {
+        let path = delta.new_file().path().or(delta.old_file().path());
+        if path.is_some_and(|path| ignored_paths.is_match(path)) {
+            continue;
+        }
+        stats.files_changed += 1;
+        if opts.hotspots || opts.coupling.is_some() {
+            stats.changed_paths.extend(delta_path(&delta));
+        }
+
+        let Ok(Some(patch)) = git2::Patch::from_diff(&diff, index) else {
+            continue;
+        };
+        // Binary files have no lines, which is only known once the patch was loaded
+        if patch.delta().flags().is_binary() {
+            stats.binary_files_changed += 1;
+            continue;
+        }
+        let (insertions, deletions) = line_stats(&patch, opts.ignore_blank_lines);
+        stats.insertions += insertions;
+        stats.deletions += deletions;
+        if opts.lang_stats {
+            let extension = path
+                .and_then(|path| path.extension())
+                .map(|extension| format!(".{}", extension.to_string_lossy()))
+                .unwrap_or_else(|| NO_EXTENSION.to_string());
+            add_lines(
+                &mut stats.lines_per_extension,
+                extension,
+                (insertions, deletions),
+            );
+        }
+        if opts.dir_stats {
+            let directory = path
+                .map(top_level_directory)
+                .unwrap_or_else(|| ROOT_DIRECTORY.to_string());
+            add_lines(
+                &mut stats.lines_per_directory,
+                directory,
+                (insertions, deletions),
+            );
+        }
+    }
+    pair_changed_paths(stats, opts)
+}
+
+/// Pairs up the changed files for the coupling, dropping their paths again if they were only collected for it
+fn pair_changed_paths(mut stats: CommitDiffStats, opts: &AnalysisOptions) -> CommitDiffStats {
+    if opts.coupling.is_some() && stats.changed_paths.len() <= MAX_COUPLED_FILES {
+        let mut paths = stats.changed_paths.clone();
+        paths.sort();
+        paths.dedup();
+        for (index, first) in paths.iter().enumerate() {
+            for second in &paths[index + 1..] {
+                stats.file_pairs.push((first.clone(), second.clone()));
+            }
+        }
+    }
+    if !opts.hotspots {
+        stats.changed_paths.clear();
+    }
+    stats
+}
+
+/// Gets the pairs of files changed together by the most commits, ties sorted by their paths
+fn top_coupled_files(
+    commits_per_file_pair: HashMap<(String, String), usize>,
+    amount: usize,
+) -> Vec<CoupledFiles> {
+    let mut pairs: Vec<CoupledFiles> = commits_per_file_pair
+        .into_iter()
+        .map(|((first, second), commits)| CoupledFiles {
+            first,
+            second,
+            commits,
+        })
+        .collect();
+    pairs.sort_by(|a, b| {
+        b.commits
+            .cmp(&a.commits)
+            .then_with(|| a.first.cmp(&b.first))
+            .then_with(|| a.second.cmp(&b.second))
+    });
+    pairs.truncate(amount);
+    pairs
+}
+
+/// Counts the inserted and deleted lines of the patch, optionally without the blank ones.
+/// libgit2 has an option to ignore blank lines, but like `git diff --ignore-blank-lines` it only hides
+/// hunks that consist of blank lines alone, and still counts them in the line stats.
+fn line_stats(patch: &git2::Patch, ignore_blank_lines: bool) -> (usize, usize) {
+    if !ignore_blank_lines {
+        let (_, insertions, deletions) = patch.line_stats().expect("Failed to get line stats");
+        return (insertions, deletions);
+    }
+    let mut lines = (0, 0);
+    for hunk in 0..patch.num_hunks() {
+        let line_amount = patch.num_lines_in_hunk(hunk).expect("Failed to get hunk");
+        for index in 0..line_amount {
+            let line = patch.line_in_hunk(hunk, index).expect("Failed to get line");
+            if line.content().trim_ascii().is_empty() {
+                continue;
+            }
+            match line.origin() {
+                '+' => lines.0 += 1,
+                '-' => lines.1 += 1,
+                _ => {}
+            }
+        }
+    }
+    lines
+}
+
+/// Gets the path of the changed file, which is the old one for deleted files
+fn delta_path(delta: &git2::DiffDelta) -> Option<String> {
+    delta
+        .new_file()
+        .path()
+        .or(delta.old_file().path())
+        .map(|path| path.to_string_lossy().into_owned())
+}
+
+/// Creates the options for diffs that only contain the changes in the pathspecs (or all, if there are none)
+fn path_diff_options(paths: &[String]) -> git2::DiffOptions {
+    let mut diff_options = git2::DiffOptions::new();
+    for path in paths {
+        diff_options.pathspec(path);
+    }
+    diff_options
+}
+
+/// Checks whether the commit changes any file matched by the pathspecs of the diff options,
+/// compared to its first parent. The first commit is compared to an empty tree.
+fn touches_paths(
+    repository: &Repository,
+    commit: &git2::Commit,
+    path_options: &mut git2::DiffOptions,
+) -> bool {
+    let parent_tree = commit.parent(0).and_then(|parent| parent.tree()).ok();
+    let tree = commit.tree().expect("Failed to get tree");
+    repository
+        .diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), Some(path_options))
+        .map(|diff| diff.deltas().len() > 0)
+        .expect("Failed to get diff")
+}
+
+/// Gets the first component of the path, or "(root)" for files directly in the root
+fn top_level_directory(path: &std::path::Path) -> String {
+    let mut components = path.components();
+    match (components.next(), components.next()) {
+        (Some(directory), Some(_)) => directory.as_os_str().to_string_lossy().into_owned(),
+        _ => ROOT_DIRECTORY.to_string(),
+    }
+}
+
+/// Checks whether the name or email of the author contains the pattern (case-insensitive)
+fn author_matches(author: &git2::Signature, pattern: &str) -> bool {
+    let pattern = pattern.to_lowercase();
+    [author.name_bytes(), author.email_bytes()]
+        .into_iter()
+        .any(|value| lossy(value).to_lowercase().contains(&pattern))
+}
+
+/// Gets the name the author is counted as, falling back to the email for commits without a name
+fn author_name(author: &git2::Signature) -> String {
+    [author.name_bytes(), author.email_bytes()]
+        .into_iter()
+        .map(lossy)
+        .map(|value| value.trim().to_string())
+        .find(|value| !value.is_empty())
+        .unwrap_or_else(|| UNKNOWN_AUTHOR.to_string())
+}
+
+/// Normalizes the name of a contributor if enabled, e.g. "  Alice\tSmith " and "Alice Smith" are the same.
+/// NFC composes characters that may also be written as a base and combining marks (e.g. "e" and U+0301 as "é").
+fn normalize_author(name: String, opts: &AnalysisOptions) -> String {
+    if !opts.normalize_authors {
+        return name;
+    }
+    let name = name.split_whitespace().collect::<Vec<_>>().join(" ");
+    let name = ComposingNormalizerBorrowed::new_nfc()
+        .normalize(&name)
+        .into_owned();
+    if opts.lowercase_authors {
+        name.to_lowercase()
+    } else {
+        name
+    }
+}
+
+/// Converts names, messages and paths that git stores as bytes (e.g. Latin-1 from old repositories) into text.
+/// Invalid UTF-8 is replaced by U+FFFD (�), so that lossy conversions stay visible instead of dropping the value.
+fn lossy(bytes: &[u8]) -> Cow<'_, str> {
+    String::from_utf8_lossy(bytes)
+}
+
+/// Checks whether the commit changes nothing compared to its parent (or is the first commit, but without files).
+/// Equal trees mean a diff without deltas, so no diff is needed. Merges are never empty, as they join histories.
+fn is_empty_commit(commit: &git2::Commit) -> bool {
+    match commit.parent_count() {
+        0 => commit.tree().is_ok_and(|tree| tree.is_empty()),
+        1 => commit
+            .parent(0)
+            .is_ok_and(|parent| parent.tree_id() == commit.tree_id()),
+        _ => false,
+    }
+}
+
+/// Converts the time of a commit into the timezone it was made in
+fn commit_local_time(time: git2::Time) -> DT<FixedOffset> {
+    let offset = FixedOffset::east_opt(time.offset_minutes() * 60)
+        .unwrap_or_else(|| FixedOffset::east_opt(0).unwrap());
+    DT::from_timestamp(time.seconds(), 0)
+        .unwrap()
+        .with_timezone(&offset)
+}
+
+/// Checks whether the subject follows the conventional commits format, i.e. matches
+/// `^(feat|fix|docs|chore|refactor|test|style|perf)(\(.+\))?:`
+fn is_conventional_commit(subject: &str) -> bool {
+    CONVENTIONAL_COMMIT_TYPES.iter().any(|commit_type| {
+        let Some(rest) = subject.strip_prefix(commit_type) else {
+            return false;
+        };
+        // Either directly followed by the colon, or by a non-empty scope in parentheses
+        rest.starts_with(':')
+            || (rest.starts_with('(') && rest.match_indices("):").any(|(i, _)| i >= 2))
+    })
+}
+
+/// Extracts the name and email of all `Co-authored-by: Name <email>` trailers (case-insensitive)
+fn co_authors(message: &str) -> Vec<(String, String)> {
+    const TRAILER: &str = "co-authored-by:";
+
+    let mut co_authors: Vec<(String, String)> = Vec::new();
+    for line in message.lines() {
+        let line = line.trim();
+        // Slice with get, as the trailer length may fall into a multibyte character of other lines
+        if !line
+            .get(..TRAILER.len())
+            .is_some_and(|prefix| prefix.eq_ignore_ascii_case(TRAILER))
+        {
+            continue;
+        }
+        let value = line.get(TRAILER.len()..).unwrap_or_default().trim();
+        let (name, email) = match value.split_once('<') {
+            Some((name, email)) => (name.trim(), email.trim_end_matches('>').trim()),
+            None => (value, ""),
+        };
+        if name.is_empty() {
+            continue;
+        }
+        // The same person may be listed multiple times with different spellings of the name
+        let is_duplicate = co_authors.iter().any(|(known_name, known_email)| {
+            if email.is_empty() || known_email.is_empty() {
+                known_name.eq_ignore_ascii_case(name)
+            } else {
+                known_email.eq_ignore_ascii_case(email)
+            }
+        });
+        if !is_duplicate {
+            co_authors.push((name.to_string(), email.to_string()));
+        }
+    }
+    co_authors
+}
+
+/// Maps a commit date onto its index in the per-day buffer of the commit graph.
+/// The buffer is in chronological order, with the last index being today.
+/// Returns `None` for dates outside the graph, i.e. on or before the same date a year ago and in the future.
+fn day_index(commit_date: NaiveDate, today: NaiveDate) -> Option<usize> {
+    // Feb 29 is moved to Feb 28 of the year before
+    let year_ago = today - Months::new(12);
+    if commit_date <= year_ago || commit_date > today {
+        return None;
+    }
+    Some(DAYS_IN_GRAPH - 1 - (today - commit_date).num_days() as usize)
+}
+
+/// Counts the active and the cumulative contributors of every month between the first and the last one
+fn contributor_growth(
+    authors_per_month: BTreeMap<(i32, u32), HashSet<String>>,
+) -> Vec<ContributorGrowth> {
+    let (Some(&first), Some(&last)) = (
+        authors_per_month.keys().next(),
+        authors_per_month.keys().next_back(),
+    ) else {
+        return Vec::new();
+    };
+
+    let mut growth = Vec::new();
+    let mut seen: HashSet<&String> = HashSet::new();
+    let (mut year, mut month) = first;
+    while (year, month) <= last {
+        let active = authors_per_month.get(&(year, month));
+        seen.extend(active.into_iter().flatten());
+        growth.push(ContributorGrowth {
+            year,
+            month,
+            active_contributors: active.map_or(0, HashSet::len),
+            total_contributors: seen.len(),
+        });
+        (year, month) = if month == 12 {
+            (year + 1, 1)
+        } else {
+            (year, month + 1)
+        };
+    }
+    growth
+}
+
+/// Computes the mean and median time between consecutive commits from the commit timestamps.
+/// The timestamps are turned into the intervals in place, to not need a second buffer on large repositories.
+fn commit_intervals(mut times: Vec<u64>) -> (Option<u64> /*mean*/, Option<u64> /*median*/) {
+    if times.len() < 2 {
+        return (None, None);
+    }
+    times.sort_unstable();
+    let mean = (times[times.len() - 1] - times[0]) / (times.len() as u64 - 1);
+
+    for i in (1..times.len()).rev() {
+        times[i] -= times[i - 1];
+    }
+    let intervals = &mut times[1..];
+    let even = intervals.len().is_multiple_of(2);
+    let middle = intervals.len() / 2;
+    let (lower, upper_median, _) = intervals.select_nth_unstable(middle);
+    let upper_median = *upper_median;
+    let median = if even {
+        // With an even amount of intervals, the largest of the lower half is the other middle value
+        let lower_median = lower.iter().max().unwrap();
+        (lower_median + upper_median) / 2
+    } else {
+        upper_median
+    };
+    (Some(mean), Some(median))
+}
+
+/// Computes the longest and the current streak of consecutive calendar days with at least one commit.
+/// The current streak is the one that includes today.
+fn compute_streaks(
+    active_days: &BTreeSet<NaiveDate>,
+    today: NaiveDate,
+) -> (usize /*longest*/, usize /*current*/) {
+    let mut longest_streak = 0;
+    let mut streak = 0;
+    let mut previous_day: Option<NaiveDate> = None;
+    for &day in active_days {
+        if previous_day.and_then(|previous| previous.succ_opt()) == Some(day) {
+            streak += 1;
+        } else {
+            streak = 1;
+        }
+        longest_streak = longest_streak.max(streak);
+        previous_day = Some(day);
+    }
+
+    // The streak that is still running today is the current one
+    let current_streak = if previous_day == Some(today) {
+        streak
+    } else {
+        0
+    };
+    (longest_streak, current_streak)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compute_streaks_distinguishes_longest_and_current() {
+        let today = NaiveDate::from_ymd_opt(2024, 6, 30).unwrap();
+        // A longer run of five active days somewhere in the middle ...
+        let mut active_days: BTreeSet<NaiveDate> = (1..=5)
+            .map(|day| NaiveDate::from_ymd_opt(2024, 3, day).unwrap())
+            .collect();
+        // ... and a shorter run of two days that is still going on today
+        active_days.insert(today.pred_opt().unwrap());
+        active_days.insert(today);
+
+        assert_eq!(compute_streaks(&active_days, today), (5, 2));
+        // Without a commit today, there is no current streak
+        assert_eq!(
+            compute_streaks(&active_days, today.succ_opt().unwrap()),
+            (5, 0)
+        );
+    }
+
+    #[test]
+    fn compute_streaks_without_commits() {
+        let today = NaiveDate::from_ymd_opt(2024, 6, 30).unwrap();
+        assert_eq!(compute_streaks(&BTreeSet::new(), today), (0, 0));
+    }
+
+    #[test]
+    fn compute_streaks_counts_calendar_days() {
+        let date = |m, d| NaiveDate::from_ymd_opt(2024, m, d).unwrap();
+        // Several commits on the same day don't extend the streak
+        let same_day: BTreeSet<NaiveDate> = [date(6, 3), date(6, 3), date(6, 4)].into();
+        assert_eq!(compute_streaks(&same_day, date(6, 4)), (2, 2));
+
+        // Thursday and Friday, then nothing over the weekend until Monday
+        let weekend_gap: BTreeSet<NaiveDate> = [date(6, 6), date(6, 7), date(6, 10)].into();
+        assert_eq!(compute_streaks(&weekend_gap, date(6, 10)), (2, 1));
+
+        // Streaks continue across months
+        let month_change: BTreeSet<NaiveDate> = [date(5, 31), date(6, 1)].into();
+        assert_eq!(compute_streaks(&month_change, date(6, 1)), (2, 2));
+    }
+
+    #[test]
+    fn commit_intervals_mean_and_median() {
+        assert_eq!(commit_intervals(vec![]), (None, None));
+        assert_eq!(commit_intervals(vec![42]), (None, None));
+        // Intervals of 10, 20 and 90 seconds, given in walk order
+        assert_eq!(commit_intervals(vec![120, 30, 0, 10]), (Some(40), Some(20)));
+        // Intervals of 10, 20, 30 and 60 seconds
+        assert_eq!(
+            commit_intervals(vec![0, 10, 30, 60, 120]),
+            (Some(30), Some(25))
+        );
+    }
+
+    #[test]
+    fn commit_local_time_applies_offset() {
+        // 2024-03-01 22:30 UTC is already 07:30 on the next day in UTC+9
+        let time = git2::Time::new(1709332200, 9 * 60);
+        assert_eq!(commit_local_time(time).hour(), 7);
+
+        // ... and still 17:30 on the same day in UTC-5
+        let time = git2::Time::new(1709332200, -5 * 60);
+        assert_eq!(commit_local_time(time).hour(), 17);
+    }
+
+    #[test]
+    fn weekday_stats_use_timezone_of_commit() {
+        let dir = tempfile::tempdir().unwrap();
+        let repository = Repository::init(dir.path()).unwrap();
+        // Friday 2024-03-01 22:30 UTC, but already Saturday for the author in UTC+9
+        let signature = git2::Signature::new(
+            "Author",
+            "author@example.com",
+            &git2::Time::new(1709332200, 9 * 60),
+        )
+        .unwrap();
+        let tree_id = repository.index().unwrap().write_tree().unwrap();
+        let tree = repository.find_tree(tree_id).unwrap();
+        repository
+            .commit(
+                Some("HEAD"),
+                &signature,
+                &signature,
+                "Late commit",
+                &tree,
+                &[],
+            )
+            .unwrap();
+
+        let opts = AnalysisOptions {
+            weekday_stats: true,
+            ..AnalysisOptions::default()
+        };
+        let stats = analyze(&repository, &opts).unwrap();
+        assert_eq!(stats.commits_per_weekday, [0, 0, 0, 0, 0, 1, 0]);
+        let saturday = NaiveDate::from_ymd_opt(2024, 3, 2)
+            .unwrap()
+            .num_days_from_ce();
+        assert_eq!(stats.weekday_window, Some((saturday, saturday)));
+    }
+
+    #[test]
+    fn author_normalization() {
+        let mut opts = AnalysisOptions::default();
+        let decomposed = "Jose\u{301}  Garc\u{131}a ".to_string();
+        assert_eq!(normalize_author(decomposed.clone(), &opts), decomposed);
+
+        opts.normalize_authors = true;
+        assert_eq!(normalize_author(decomposed.clone(), &opts), "José Garcıa");
+        assert_eq!(normalize_author(" ALICE\t".to_string(), &opts), "ALICE");
+        opts.lowercase_authors = true;
+        assert_eq!(normalize_author(" ALICE\t".to_string(), &opts), "alice");
+        assert_eq!(normalize_author(decomposed, &opts), "josé garcıa");
+    }
+
+    #[test]
+    fn co_authors_from_trailers() {
+        let message = "Pair on the parser
+
+Co-authored-by: Alice <alice@example.com>
+co-authored-by: Bob <bob@example.com>
+CO-AUTHORED-BY: alice <ALICE@example.com>
+Signed-off-by: Carol <carol@example.com>";
+        assert_eq!(
+            co_authors(message),
+            vec![
+                ("Alice".to_string(), "alice@example.com".to_string()),
+                ("Bob".to_string(), "bob@example.com".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn co_authors_of_non_ascii_lines() {
+        // Byte 15 of the first line falls into the 'ö'
+        let message = "Vereinfache Größenprüfung
+
+Co-authored-by: Jürgen <juergen@example.com>
+Zusammengeführt mit 🦀 am Montag";
+        assert_eq!(
+            co_authors(message),
+            vec![("Jürgen".to_string(), "juergen@example.com".to_string())]
+        );
+    }
+
+    #[test]
+    fn conventional_commit_subjects() {
+        assert!(is_conventional_commit("feat: add message stats"));
+        assert!(is_conventional_commit("fix(parser): handle empty values"));
+        assert!(!is_conventional_commit("fix(): empty scope"));
+        assert!(!is_conventional_commit("feature: unknown type"));
+        assert!(!is_conventional_commit("Fix: wrong case"));
+        assert!(!is_conventional_commit("docs update readme"));
+    }
+
+    #[test]
+    fn analyze_empty_repository() {
+        let dir = tempfile::tempdir().unwrap();
+        let repository = Repository::init(dir.path()).unwrap();
+        let opts = AnalysisOptions {
+            extended_stats: true,
+            commit_graph: true,
+            weekday_stats: true,
+            hour_stats: true,
+            message_stats: true,
+            lang_stats: true,
+            ..AnalysisOptions::default()
+        };
+
+        let stats = analyze(&repository, &opts).unwrap();
+        assert_eq!(stats.commit_count, 0);
+        assert!(stats.contributors.is_empty());
+    }
+
+    /// Writes the files into the working directory and commits them on top of HEAD
+    fn commit_files(repository: &Repository, files: &[(&str, &str)], seconds: i64) -> Oid {
+        let workdir = repository.workdir().unwrap();
+        let mut index = repository.index().unwrap();
+        for (path, content) in files {
+            let file = workdir.join(path);
+            std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+            std::fs::write(file, content).unwrap();
+            index.add_path(std::path::Path::new(path)).unwrap();
+        }
+        index.write().unwrap();
+        let tree = repository.find_tree(index.write_tree().unwrap()).unwrap();
+
+        let signature =
+            git2::Signature::new("A", "a@example.com", &git2::Time::new(seconds, 0)).unwrap();
+        let parent = repository
+            .head()
+            .and_then(|head| head.peel_to_commit())
+            .ok();
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        repository
+            .commit(
+                Some("HEAD"),
+                &signature,
+                &signature,
+                "Commit",
+                &tree,
+                &parents,
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn analyze_commits_out_of_topological_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let repository = Repository::init(dir.path()).unwrap();
+
+        // The second commit claims to be older than its parent, e.g. due to a rebase or a wrong clock
+        let now = Local::now().timestamp();
+        for seconds in [now - 86_400, now - 3 * 86_400, now] {
+            commit_files(&repository, &[], seconds);
+        }
+
+        let opts = AnalysisOptions {
+            commit_graph: true,
+            ..AnalysisOptions::default()
+        };
+        let stats = analyze(&repository, &opts).unwrap();
+        assert_eq!(stats.commit_count, 3);
+        assert_eq!(stats.first_commit, (now - 3 * 86_400) as u64);
+        assert_eq!(stats.last_commit, now as u64);
+        assert_eq!(stats.commits_last_year, 3);
+        // Today and yesterday, with a gap before the commit three days ago
+        assert_eq!(stats.longest_commit_streak, 2);
+        assert_eq!(stats.current_commit_streak, 2);
+    }
+
+    #[test]
+    fn ignored_paths_are_left_out_of_churn() {
+        let dir = tempfile::tempdir().unwrap();
+        let repository = Repository::init(dir.path()).unwrap();
+        commit_files(&repository, &[("README.md", "Readme\n")], 0);
+        commit_files(
+            &repository,
+            &[
+                ("Cargo.lock", "a\nb\nc\n"),
+                ("src/main.rs", "fn main() {}\n"),
+            ],
+            60,
+        );
+
+        let opts = AnalysisOptions {
+            extended_stats: true,
+            dir_stats: true,
+            ignore_paths: vec!["*.lock".to_string()],
+            ..AnalysisOptions::default()
+        };
+        let stats = analyze(&repository, &opts).unwrap();
+        assert_eq!(stats.commit_count, 2);
+        assert_eq!(stats.total_files_changes, 1);
+        assert_eq!(stats.total_lines_inserted, 1);
+        assert_eq!(
+            stats.lines_per_directory,
+            HashMap::from([("src".to_string(), (1, 0))])
+        );
+
+        let opts = AnalysisOptions {
+            ignore_paths: vec!["[".to_string()],
+            ..AnalysisOptions::default()
+        };
+        assert!(matches!(
+            analyze(&repository, &opts),
+            Err(RepolyzerError::BadArgs(_))
+        ));
+    }
+
+    #[test]
+    fn contributor_growth_fills_empty_months() {
+        let authors = |names: &[&str]| names.iter().map(|name| name.to_string()).collect();
+        let authors_per_month = BTreeMap::from([
+            ((2023, 11), authors(&["A"])),
+            ((2024, 2), authors(&["A", "B"])),
+        ]);
+        let growth: Vec<(i32, u32, usize, usize)> = contributor_growth(authors_per_month)
+            .into_iter()
+            .map(|month| {
+                (
+                    month.year,
+                    month.month,
+                    month.active_contributors,
+                    month.total_contributors,
+                )
+            })
+            .collect();
+        assert_eq!(
+            growth,
+            [
+                (2023, 11, 1, 1),
+                (2023, 12, 0, 1),
+                (2024, 1, 0, 1),
+                (2024, 2, 2, 2)
+            ]
+        );
+    }
+
+    #[test]
+    fn path_filter_limits_commits_and_churn() {
+        let dir = tempfile::tempdir().unwrap();
+        let repository = Repository::init(dir.path()).unwrap();
+        commit_files(&repository, &[("services/payments/a.rs", "a\n")], 0);
+        commit_files(&repository, &[("README.md", "Readme\n")], 60);
+        commit_files(
+            &repository,
+            &[
+                ("services/payments/a.rs", "a\nb\n"),
+                ("README.md", "Readme\nMore\n"),
+            ],
+            120,
+        );
+
+        let opts = AnalysisOptions {
+            extended_stats: true,
+            paths: vec!["services/payments/".to_string()],
+            ..AnalysisOptions::default()
+        };
+        let stats = analyze(&repository, &opts).unwrap();
+        // The first commit has no parent to be diffed to, but still touches the path
+        assert_eq!(stats.commit_count, 2);
+        assert_eq!(stats.total_files_changes, 1);
+        assert_eq!(stats.total_lines_inserted, 1);
+    }
+
+    #[test]
+    fn compare_only_analyzes_commits_of_range() {
+        let dir = tempfile::tempdir().unwrap();
+        let repository = Repository::init(dir.path()).unwrap();
+        let base = commit_files(&repository, &[("a.txt", "a\n")], 0);
+        repository
+            .tag_lightweight("v1", &repository.find_object(base, None).unwrap(), false)
+            .unwrap();
+        commit_files(&repository, &[("a.txt", "a\nb\n")], 60);
+        commit_files(&repository, &[("a.txt", "a\nb\nc\n")], 120);
+
+        let opts = AnalysisOptions {
+            compare: Some("v1..HEAD".to_string()),
+            ..AnalysisOptions::default()
+        };
+        let stats = analyze(&repository, &opts).unwrap();
+        assert_eq!(stats.commit_count, 2);
+        assert_eq!(stats.first_commit, 60);
+        assert_eq!(stats.compared_range.as_deref(), Some("v1..HEAD"));
+
+        for range in ["v1", "v1...HEAD", "..HEAD", "v2..HEAD"] {
+            assert!(matches!(
+                resolve_range(&repository, range),
+                Err(RepolyzerError::RefNotFound(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn range_walks_revisions() {
+        let dir = tempfile::tempdir().unwrap();
+        let repository = Repository::init(dir.path()).unwrap();
+        let base = commit_files(&repository, &[("a.txt", "a\n")], 0);
+        repository
+            .tag_lightweight("v1", &repository.find_object(base, None).unwrap(), false)
+            .unwrap();
+        commit_files(&repository, &[("a.txt", "a\nb\n")], 60);
+        commit_files(&repository, &[("a.txt", "a\nb\nc\n")], 120);
+
+        let commits = |spec: &str| {
+            let opts = AnalysisOptions {
+                range: Some(spec.to_string()),
+                ..AnalysisOptions::default()
+            };
+            analyze(&repository, &opts).map(|stats| stats.commit_count)
+        };
+        assert_eq!(commits("HEAD~1..HEAD").unwrap(), 1);
+        assert_eq!(commits("v1..").unwrap(), 2);
+        assert_eq!(commits("HEAD~1").unwrap(), 2);
+        assert_eq!(commits("v1...HEAD").unwrap(), 2);
+
+        for spec in ["v2..HEAD", "HEAD..HEAD^{tree}", "HEAD~5"] {
+            assert!(matches!(commits(spec), Err(RepolyzerError::BadRange(_))));
+        }
+    }
+
+    #[test]
+    fn binary_files_are_counted_separately() {
+        let dir = tempfile::tempdir().unwrap();
+        let repository = Repository::init(dir.path()).unwrap();
+        commit_files(&repository, &[("README.md", "Readme\n")], 0);
+        commit_files(
+            &repository,
+            &[
+                ("logo.png", "\u{0}PNG\u{0}\u{1}\u{2}"),
+                ("README.md", "Readme\nLogo\n"),
+            ],
+            60,
+        );
+
+        // Both with the plain diff stats and when looking at every file
+        for lang_stats in [false, true] {
+            let opts = AnalysisOptions {
+                extended_stats: true,
+                lang_stats,
+                ..AnalysisOptions::default()
+            };
+            let stats = analyze(&repository, &opts).unwrap();
+            assert_eq!(stats.total_files_changes, 2);
+            assert_eq!(stats.binary_files_changed, 1);
+            assert_eq!(stats.total_lines_inserted, 1);
+        }
+    }
+
+    #[test]
+    fn top_level_directory_of_paths() {
+        let path = std::path::Path::new;
+        assert_eq!(top_level_directory(path("src/main.rs")), "src");
+        assert_eq!(
+            top_level_directory(path("crates/core/src/lib.rs")),
+            "crates"
+        );
+        assert_eq!(top_level_directory(path("README.md")), ROOT_DIRECTORY);
+    }
+
+    #[test]
+    fn day_index_spans_leap_day() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        let today = date(2024, 3, 1);
+
+        assert_eq!(day_index(today, today), Some(DAYS_IN_GRAPH - 1));
+        assert_eq!(day_index(date(2024, 2, 29), today), Some(DAYS_IN_GRAPH - 2));
+        assert_eq!(day_index(date(2024, 2, 28), today), Some(DAYS_IN_GRAPH - 3));
+        // The same date a year ago is 366 days back due to Feb 29 and therefore no longer part of the graph
+        assert_eq!(day_index(date(2023, 3, 2), today), Some(0));
+        assert_eq!(day_index(date(2023, 3, 1), today), None);
+        // Commits from the future are ignored as well
+        assert_eq!(day_index(date(2024, 3, 2), today), None);
+
+        // Without a leap day, the year only fills the graph up from the second day
+        let today = date(2025, 6, 30);
+        assert_eq!(day_index(date(2024, 7, 1), today), Some(1));
+        assert_eq!(day_index(date(2024, 6, 30), today), None);
+
+        // On a leap day, the year starts after Feb 28 and fills the whole graph
+        let today = date(2024, 2, 29);
+        assert_eq!(day_index(date(2023, 3, 1), today), Some(0));
+        assert_eq!(day_index(date(2023, 2, 28), today), None);
+    }
+}
@@ -1,584 +1,4270 @@
-use chrono::{DateTime as DT, Datelike as DL, Local};
-use git2::Repository;
+mod html;
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime as DT, Datelike as DL, Local, Months, NaiveDate, SecondsFormat};
+use git2::{Cred, ErrorCode, RemoteCallbacks, Repository, RepositoryOpenFlags};
 use piechart::{Chart, Color, Data};
+use repolyzer::{
+    analyze_with_commits, resolve_range, resolve_revisions, AnalysisOptions, ChurnMerges,
+    CoauthorMode, CommitRecord, DiffAlgorithm, Identity, RepolyzerError, RepositoryStats,
+    DAYS_IN_GRAPH, EXIT_NO_COMMITS, MAX_DAYS_IN_YEAR, MAX_MESSAGE_LINE_LENGTH, QUARTER_DAYS,
+};
+use serde::Deserialize;
+use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::fmt;
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufWriter, IsTerminal, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use std::{path::PathBuf, process::exit};
 use url::Url;
 
 // ------------------------- Constants
-const HELP: &str = "Usage: repolyzer [OPTIONS] <PATH>
+const HELP: &str = "Usage: repolyzer [OPTIONS] <PATH>...
 
 Analyze a Git repository and display statistics about it.
 
 OPTIONS:
     -c, --commit-graph        Enable the commit graph (similar to GitHub's)
+        --heatmap-all         Enable a commit graph for every year of the whole history
     -e, --extended-overview  *Enables the extended overview instead of the general one
     -n, --no-overview         Disable the general overview
     -p, --pie-chart           Enable the pie chart
+        --contributors        List the contributors ranked by their commits (the first n of --top)
     -w, --week-day-stats     *Enable the week day stats
+        --normalize-weekdays  Show the week day stats (-w) as commits per occurrence of the week day between the first
+                              and last commit, instead of the total commits
+        --hour-stats          Enable the commits per hour of the day (in the timezone of the commit)
+        --message-stats       Enable the commit message stats (subject length, conventional commits, reverts, fixups)
+        --signatures          Enable the share of GPG/SSH signed commits (the signatures are not verified)
+        --lang-stats         *Enable the changed lines per file extension
+        --dir-stats          *Enable the changed lines per top-level directory
+        --size-histogram     *Enable the histogram of commits by their changed lines (to spot giant commits)
+        --hotspots           *List the files changed by the most commits (churn hotspots, e.g. to find refactoring candidates)
+        --coupling [n]       *List the n pairs of files (default: 10) changed together by the most commits, e.g. to find
+                              hidden dependencies. Commits changing more than 50 files are left out
+        --tags                Enable the tag stats (amount of tags and the latest one)
+        --branches            List the local branches with their commits ahead/behind HEAD
+        --author-timeline     List the first and last commit of every contributor, and whether they committed in the last 90 days
+        --contributor-classes [n]
+                              Split the contributors into core ones with at least n commits (default: 10) or a commit in the
+                              last 6 months, and occasional ones, with their shares of the commits
+        --growth              Enable the active and total contributors per month
+        --largest-files [n]   List the n largest files (default: 10) in the tree of HEAD (or --ref), not in the history
+        --repo-size          *Enable the amount of commit, tree, blob and tag objects and the size of the .git directory
+                              (for remote repositories, the size of the fresh clone)
+        --ref <name>          Analyze the given branch, tag or revision instead of HEAD
+        --compare <base>..<head>
+                              Only analyze the commits reachable from head but not from base, e.g. 'v1.0..main'
+        --range <spec>        Only analyze the commits of a gitrevisions range, e.g. 'HEAD~100..HEAD', 'main...feature'
+                              (reachable from either, but not both) or 'v1.0' (its whole history)
+        --author <pattern>    Only analyze commits whose author name or email contains the pattern
+        --exclude-author <pattern>
+                              Skip commits whose author name or email contains the pattern (repeatable, e.g. for bots)
+        --ignore-path <glob>  Leave the changes of matching files (e.g. '*.lock') out of the changed lines stats (repeatable),
+                              the commits themselves are still counted
+        --sample <rate>       Only diff a share (e.g. 0.1) of the commits for the changed lines stats and extrapolate them.
+                              The commits are picked by their id, so reruns give the same estimates. Other stats stay exact
+        --churn-merges <mode> Leave merge commits out of the changed lines stats with 'skip' (default), or diff them
+                              against their first parent with 'first-parent', which counts the merged changes twice
+        --diff-algorithm <name>
+                              Count the changed lines with the 'myers' (default, like git), 'patience' or 'minimal' diff,
+                              e.g. to compare them with other tools ('histogram' is not supported by libgit2)
+        --ignore-whitespace   Do not count lines that only differ in whitespace as changed, e.g. for reformatting commits
+        --ignore-blank-lines  Do not count inserted or removed blank lines (or ones with only whitespace) as changed
+        --no-ignore           Count every changed line, including whitespace and blank lines (default, overrides the config)
+        --normalize-authors   Count contributor names that only differ in whitespace or Unicode composition as one,
+                              e.g. ' Alice ' and 'Alice' (a quick alternative to a .mailmap)
+        --lowercase-authors   Also count names that only differ in case as one, shown in lowercase (implies --normalize-authors)
+        --coauthors <mode>    Also credit the co-authors of 'Co-authored-by:' trailers, with a 'full' commit each or 'split' evenly
+        --by <identity>       Credit the commits to their 'author' (default) or 'committer', e.g. to see who rebased them
+        --since <YYYY-MM-DD>  Only analyze commits made on or after the given date
+        --until <YYYY-MM-DD>  Only analyze commits made on or before the given date
+        --path <pathspec>     Only analyze commits changing files in the path, and only their changes there (repeatable)
+        --repos-from <file>   Also analyze the paths or URLs listed in the file ('-' for stdin), see PATH
+        --recurse-submodules  Also analyze the initialized submodules (recursively), listed separately and in a total
+        --limit <n>           Only analyze the n most recent commits (that match the other filters)
+        --first-parent        Only follow the first parent of merge commits, i.e. the mainline history
+        --no-merges           Skip merge commits in all stats, e.g. to not count merged changes twice
+        --no-empty            Skip commits without changes (e.g. made with --allow-empty in CI) in all stats
+        --depth <n>           Only clone the latest n commits of remote repositories (shallow clone)
+        --retries <n>         Retry failed clones of remote repositories up to n times, waiting longer each time (default: 2)
+        --timeout <secs>      Abort a clone attempt of a remote repository that takes longer than the given seconds
+        --top <n>             Name the n contributors with the most commits in the pie chart and list (default: 5)
+        --min-commits <n>     Only name contributors with at least n commits in the pie chart and list (default: 1)
+        --date-format <format>
+                              Format the dates with the given strftime format (default: '%Y-%m-%d %H:%M:%S', ISO 8601)
+        --format <format>     Print the stats as 'text' (default), 'markdown' (e.g. for GitHub issues), 'json'
+                              or 'html' (a single file report, e.g. '--format html --output report.html').
+                              With --output-dir, multiple formats and 'csv' may be combined, e.g. 'html,csv,json'
+        --json                Same as '--format json'
+        --theme <name>        Draw the commit graph with 'unicode' (default, ~ · ▪ ● ⬟), 'ascii' (. : + # @)
+                              or 'blocks' (· ░ ▒ ▓ █) symbols, e.g. for limited fonts or CI logs
+        --width <cols>        Fit the bars and the commit graph into the given columns
+                              (default: the width of the terminal, or 80 if not printing to one)
+        --ascii, --no-color   Render the pie chart as a plain text list (also enabled by the NO_COLOR env var)
+        --quiet               Do not show the progress indicator
+        --deterministic       Produce the same output on every run (e.g. for snapshot tests), without progress and with
+                              REPOLYZER_NOW (seconds since the epoch, default: 2024-01-01) as the current time.
+                              Also enabled by REPOLYZER_DETERMINISTIC=1, dates stay in the local timezone (see TZ)
+        --live                Show the commit amount and the last commit found so far in the progress indicator
+        --watch               Keep running and analyze local repositories again whenever their commits or refs change
+        --watch-interval <secs>
+                              Check for changes in watch mode every n seconds (default: 5)
+        --no-cache            Always analyze the repository, instead of reusing cached stats of an unchanged one
+        --output <path>       Write the stats into the given file instead of stdout
+        --output-dir <dir>    Write a file per format of --format into the directory, analyzing the repositories once:
+                              report.txt, report.md, stats.json, report.html and daily.csv (commits per day, with -c)
+        --csv <path>          Write the commits per day of the commit graph (-c) into the given CSV file
+        --dump-commits <path> Write every analyzed commit as a line of JSON into the given file (with -e including its changed lines)
+        --force               Overwrite the files of --output, --output-dir, --csv and --dump-commits if they already exist
 
 Options marked with a '*' may take more time and resources to compute, depending on the size of the repository.
 
 PATH:
     The path to the Git repository to analyze. This can be a local path or a remote URL.
-    If a remote URL is provided, the repository will be cloned to a temporary directory.";
-const UNKNOWN_AUTHOR: &str = ">UNKNOWN<";
-const SECONDS_PER_YEAR: u64 = 31_536_000;
-const SECONDS_PER_DAY: u64 = 86_400;
+    Remote URLs may use HTTP(S) or SSH ('git@host:org/repo.git' or 'ssh://git@host/org/repo.git').
+    Private HTTPS repositories are cloned with the credentials of the git credential helper (as with 'git clone'),
+    or else with the access token in the GIT_TOKEN or GITHUB_TOKEN env var.
+    If a remote URL is provided, the repository will be cloned to a temporary directory.
+    Multiple paths may be passed, in which case a summary of all repositories is printed at the end
+    (with JSON, an array of the stats of each repository is printed instead).
+    Further paths can be read with '--repos-from <file>' (or '-' for stdin), one per line. Blank lines and lines
+    starting with '#' are skipped.
+
+CONFIG:
+    Defaults for the flags, --top, --min-commits, --largest-files, --coupling, --contributor-classes,
+    --width and --date-format can be set in a 'repolyzer.toml' in the current directory, or else in
+    '$XDG_CONFIG_HOME/repolyzer/repolyzer.toml' (usually '~/.config/repolyzer/repolyzer.toml').
+    The keys are the long flag names without dashes in front, e.g. 'pie-chart = true' or 'top = 10'.
+    Flags on the command line take precedence over the file, which takes precedence over the built-in defaults.
+
+EXIT CODES:
+    0  Success
+    1  Invalid arguments or configuration file
+    2  The local repository could not be opened
+    3  The remote repository could not be cloned
+    4  The ref passed to --ref, --compare or --range could not be resolved
+    5  None of the analyzed repositories has any (matching) commits, the stats are still printed
+    6  The stats could not be written";
+const CONFIG_FILE_NAME: &str = "repolyzer.toml";
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+const DEFAULT_LARGEST_FILES: usize = 10;
+const DEFAULT_COUPLED_FILES: usize = 10;
+const DEFAULT_CORE_COMMITS: usize = 10;
+/// Name of the commits per day exported into --output-dir
+const DAILY_CSV_FILE_NAME: &str = "daily.csv";
+/// Contributors with a commit in this many days before the analysis are active in the author timeline
+const ACTIVE_DAYS: i64 = 90;
+/// Contributors with a commit in this many days (about six months) before the analysis are core contributors
+const CORE_ACTIVE_DAYS: i64 = 182;
+/// Current time of --deterministic without REPOLYZER_NOW, 2024-01-01 00:00:00 UTC
+const DETERMINISTIC_NOW: i64 = 1_704_067_200;
+/// Entries listed in the language, directory and hotspot stats
+const LISTED_ENTRIES: usize = 10;
+/// Version of the JSON output, to be increased whenever a field is renamed, removed or changes its meaning
+const JSON_SCHEMA_VERSION: u32 = 1;
+/// Columns of the text output if neither --width is given nor a terminal is printed to
+const DEFAULT_WIDTH: usize = 80;
+/// Columns in front of the bars, i.e. the tab separated label and value followed by '|'
+const BAR_LABEL_WIDTH: usize = 25;
+/// Columns in front of the checker board, i.e. '║', the weekday and a tab
+const CHECKERBOARD_LABEL_WIDTH: usize = 8;
+// Environment variables that may hold an access token for private HTTPS repositories, in order of precedence
+const TOKEN_ENV_VARS: [&str; 2] = ["GIT_TOKEN", "GITHUB_TOKEN"];
 const CHECKERBOARD_SYMBOL_AMOUNT: usize = 5;
-// None, low, more, even more, a lot
-const SYMBOLS: [char; CHECKERBOARD_SYMBOL_AMOUNT] = ['~', '·', '▪', '●', '⬟'];
+// Red, blue, yellow, teal, purple and orange
+const PIE_CHART_COLORS: [(u8, u8, u8); 6] = [
+    (255, 99, 132),
+    (54, 162, 235),
+    (255, 206, 86),
+    (75, 192, 192),
+    (153, 102, 255),
+    (255, 159, 64),
+];
 // -------------------------
 
 /// Holds the location for a given local or remote git repository
+#[derive(Clone)]
 enum GitLocation {
     Local(PathBuf),
     Remote(Url),
+    RemoteSsh(String),
+}
+
+impl fmt::Display for GitLocation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GitLocation::Local(path) => write!(f, "{}", path.display()),
+            GitLocation::Remote(url) => write!(f, "{}", url),
+            GitLocation::RemoteSsh(url) => write!(f, "{}", url),
+        }
+    }
+}
+
+/// How the stats are printed
+#[derive(Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum OutputFormat {
+    Text,
+    Markdown,
+    Json,
+    Html,
+}
+
+/// Symbols of the commit checker board
+#[derive(Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Theme {
+    Unicode,
+    Ascii,
+    Blocks,
+}
+
+impl Theme {
+    /// The symbols for none, low, more, even more and a lot of commits a day
+    fn symbols(self) -> [char; CHECKERBOARD_SYMBOL_AMOUNT] {
+        match self {
+            Theme::Unicode => ['~', '·', '▪', '●', '⬟'],
+            Theme::Ascii => ['.', ':', '+', '#', '@'],
+            Theme::Blocks => ['·', '░', '▒', '▓', '█'],
+        }
+    }
 }
 
 /// Holds parsed app arguments
+#[derive(Clone)]
 struct AppArgs {
-    location: GitLocation,
+    locations: Vec<GitLocation>,
+
+    // What to analyze
+    analysis: AnalysisOptions,
 
     // Flags
     general_overview: bool,
-    extended_overview: bool,
     pie_chart: bool,
-    commit_graph: bool,
-    weekday_stats: bool,
+    contributors: bool,
+    format: OutputFormat,
+    theme: Theme,
+    date_format: String,
+    ascii: bool,
+    normalize_weekdays: bool,
+    quiet: bool,
+    /// Byte-stable output for snapshot tests, with a fixed current time and without progress
+    deterministic: bool,
+    force: bool,
+    no_cache: bool,
+    watch: bool,
+    recurse_submodules: bool,
+
+    // Options
+    depth: Option<i32>,
+    retries: u32,
+    timeout: Option<u64>,
+    watch_interval: u64,
+    top: usize,
+    min_commits: usize,
+    /// Columns to fit the text output into, detected from the terminal if not given
+    width: Option<usize>,
+    output: Option<PathBuf>,
+    /// Directory to write a file per format of `exports` into, instead of printing to stdout
+    output_dir: Option<PathBuf>,
+    /// Formats passed to --format, all of which are exported with --output-dir
+    exports: Vec<OutputFormat>,
+    /// Whether 'csv' was passed to --format, which exports the commits per day
+    export_csv: bool,
+    dump_commits: Option<PathBuf>,
+    csv: Option<PathBuf>,
 }
 
-struct RepositoryStats {
-    // General stats
-    commit_count: usize,
-    last_commit: u64,
-    contributors: HashMap<String, u64>,
+impl AppArgs {
+    /// Creates the arguments with all defaults, as if only a path was passed
+    fn new() -> Self {
+        AppArgs {
+            locations: Vec::new(),
 
-    // Extended stats
-    total_files_changes: usize,
-    total_lines_inserted: usize,
-    total_lines_removed: usize,
+            analysis: AnalysisOptions::default(),
 
-    // Checkerboard stats
-    commits_last_year: usize,
-    longest_commit_streak: usize,
-    current_commit_streak: usize,
-    max_commits_a_day: usize,
-    commits_per_day_last_year: [usize; 365],
+            general_overview: true,
+            pie_chart: false,
+            contributors: false,
+            format: OutputFormat::Text,
+            theme: Theme::Unicode,
+            date_format: DEFAULT_DATE_FORMAT.to_string(),
+            // See https://no-color.org/
+            ascii: std::env::var("NO_COLOR").is_ok_and(|value| !value.is_empty()),
+            deterministic: std::env::var("REPOLYZER_DETERMINISTIC").is_ok_and(|value| value == "1"),
+            normalize_weekdays: false,
+            quiet: false,
+            force: false,
+            no_cache: false,
+            watch: false,
+            recurse_submodules: false,
 
-    // Weekday stats
-    commits_per_weekday: [usize; 7],
+            depth: None,
+            retries: 2,
+            timeout: None,
+            watch_interval: 5,
+            top: 5,
+            min_commits: 1,
+            width: None,
+            output: None,
+            output_dir: None,
+            exports: Vec::new(),
+            export_csv: false,
+            dump_commits: None,
+            csv: None,
+        }
+    }
 }
 
-fn main() {
-    println!("Welcome to Repolyzer!");
-    println!("Performing analysis...");
+/// Defaults for the flags, as read from the configuration file.
+/// The keys are the long names of the flags, e.g. `pie-chart = true` or `top = 10`.
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+struct Config {
+    commit_graph: Option<bool>,
+    heatmap_all: Option<bool>,
+    extended_overview: Option<bool>,
+    no_overview: Option<bool>,
+    pie_chart: Option<bool>,
+    contributors: Option<bool>,
+    week_day_stats: Option<bool>,
+    normalize_weekdays: Option<bool>,
+    hour_stats: Option<bool>,
+    message_stats: Option<bool>,
+    signatures: Option<bool>,
+    lang_stats: Option<bool>,
+    dir_stats: Option<bool>,
+    size_histogram: Option<bool>,
+    hotspots: Option<bool>,
+    growth: Option<bool>,
+    author_timeline: Option<bool>,
+    repo_size: Option<bool>,
+    ignore_whitespace: Option<bool>,
+    ignore_blank_lines: Option<bool>,
+    tags: Option<bool>,
+    branches: Option<bool>,
+    format: Option<OutputFormat>,
+    theme: Option<Theme>,
+    by: Option<Identity>,
+    date_format: Option<String>,
+    ascii: Option<bool>,
+    quiet: Option<bool>,
+    live: Option<bool>,
+    force: Option<bool>,
+    no_cache: Option<bool>,
+    recurse_submodules: Option<bool>,
+    top: Option<usize>,
+    min_commits: Option<usize>,
+    largest_files: Option<usize>,
+    coupling: Option<usize>,
+    contributor_classes: Option<usize>,
+    width: Option<usize>,
+}
 
-    let app_args: AppArgs = parse_args();
-    let repository: Repository = load_repository(&app_args.location);
+impl Config {
+    /// Loads the first configuration file found in the current directory or the XDG config directory.
+    /// Without any configuration file, the built-in defaults are kept.
+    fn load() -> Result<Self, RepolyzerError> {
+        let Some(path) = Config::find() else {
+            return Ok(Config::default());
+        };
+        let content = std::fs::read_to_string(&path).map_err(|e| {
+            RepolyzerError::BadConfig(format!("Failed to read '{}': {}", path.display(), e))
+        })?;
+        let config: Config = toml::from_str(&content).map_err(|e| {
+            RepolyzerError::BadConfig(format!("Invalid config '{}': {}", path.display(), e))
+        })?;
+        for (key, value) in [
+            ("top", config.top),
+            ("min-commits", config.min_commits),
+            ("largest-files", config.largest_files),
+            ("coupling", config.coupling),
+            ("contributor-classes", config.contributor_classes),
+            ("width", config.width),
+        ] {
+            if value == Some(0) {
+                return Err(RepolyzerError::BadConfig(format!(
+                    "Invalid config '{}': '{}' must be greater than zero",
+                    path.display(),
+                    key
+                )));
+            }
+        }
+        Ok(config)
+    }
 
-    let stats = gather_stats(repository, &app_args);
+    /// Searches the configuration file in the current directory, then in $XDG_CONFIG_HOME/repolyzer (~/.config/repolyzer)
+    fn find() -> Option<PathBuf> {
+        let local = PathBuf::from(CONFIG_FILE_NAME);
+        if local.is_file() {
+            return Some(local);
+        }
 
-    if app_args.general_overview && !app_args.extended_overview {
-        print_general_overview(&stats)
+        let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+        let global = config_dir.join("repolyzer").join(CONFIG_FILE_NAME);
+        global.is_file().then_some(global)
     }
 
-    if app_args.extended_overview {
-        print_extended_overview(&stats);
+    /// Applies the configured values onto the arguments, which are then overridden by the command line
+    fn apply(self, app_args: &mut AppArgs) {
+        let flags = [
+            (self.commit_graph, &mut app_args.analysis.commit_graph),
+            (self.heatmap_all, &mut app_args.analysis.all_time_heatmap),
+            (
+                self.extended_overview,
+                &mut app_args.analysis.extended_stats,
+            ),
+            (self.pie_chart, &mut app_args.pie_chart),
+            (self.contributors, &mut app_args.contributors),
+            (self.week_day_stats, &mut app_args.analysis.weekday_stats),
+            (self.normalize_weekdays, &mut app_args.normalize_weekdays),
+            (self.hour_stats, &mut app_args.analysis.hour_stats),
+            (self.message_stats, &mut app_args.analysis.message_stats),
+            (self.signatures, &mut app_args.analysis.signature_stats),
+            (self.lang_stats, &mut app_args.analysis.lang_stats),
+            (self.dir_stats, &mut app_args.analysis.dir_stats),
+            (self.size_histogram, &mut app_args.analysis.size_histogram),
+            (self.hotspots, &mut app_args.analysis.hotspots),
+            (self.growth, &mut app_args.analysis.growth_stats),
+            (self.author_timeline, &mut app_args.analysis.author_timeline),
+            (self.repo_size, &mut app_args.analysis.repo_size),
+            (
+                self.ignore_whitespace,
+                &mut app_args.analysis.ignore_whitespace,
+            ),
+            (
+                self.ignore_blank_lines,
+                &mut app_args.analysis.ignore_blank_lines,
+            ),
+            (self.tags, &mut app_args.analysis.tag_stats),
+            (self.branches, &mut app_args.analysis.branch_stats),
+            (self.ascii, &mut app_args.ascii),
+            (self.quiet, &mut app_args.quiet),
+            (self.live, &mut app_args.analysis.live_stats),
+            (self.force, &mut app_args.force),
+            (self.no_cache, &mut app_args.no_cache),
+            (self.recurse_submodules, &mut app_args.recurse_submodules),
+        ];
+        for (value, flag) in flags {
+            if let Some(value) = value {
+                *flag = value;
+            }
+        }
+        if let Some(no_overview) = self.no_overview {
+            app_args.general_overview = !no_overview;
+        }
+        if let Some(top) = self.top {
+            app_args.top = top;
+        }
+        if let Some(min_commits) = self.min_commits {
+            app_args.min_commits = min_commits;
+        }
+        if let Some(largest_files) = self.largest_files {
+            app_args.analysis.largest_files = Some(largest_files);
+        }
+        if let Some(coupling) = self.coupling {
+            app_args.analysis.coupling = Some(coupling);
+        }
+        if let Some(min_core_commits) = self.contributor_classes {
+            app_args.analysis.contributor_classes = Some(min_core_commits);
+        }
+        if let Some(width) = self.width {
+            app_args.width = Some(width);
+        }
+        if let Some(format) = self.format {
+            app_args.format = format;
+        }
+        if let Some(theme) = self.theme {
+            app_args.theme = theme;
+        }
+        if let Some(identity) = self.by {
+            app_args.analysis.identity = identity;
+        }
+        if let Some(date_format) = self.date_format {
+            app_args.date_format = date_format;
+        }
     }
+}
 
-    if app_args.pie_chart {
-        print_pie_chart(&stats);
+fn main() {
+    match run() {
+        Ok(exit_code) => {
+            if exit_code != 0 {
+                exit(exit_code);
+            }
+        }
+        Err(e) => {
+            match e {
+                RepolyzerError::NoArgs => println!("{}", HELP),
+                RepolyzerError::BadArgs(_) => println!("{}\n{}", e, HELP),
+                _ => println!("{}", e),
+            }
+            exit(e.exit_code());
+        }
     }
+}
 
-    if app_args.commit_graph {
-        print_commit_checker_board(&stats);
+/// Runs the whole analysis and prints the enabled stats, over and over again in watch mode.
+/// Returns the exit code, which is only non-zero if there were no commits to analyze.
+fn run() -> Result<i32, RepolyzerError> {
+    let app_args: AppArgs = parse_args()?;
+    if app_args.format == OutputFormat::Text {
+        println!("Welcome to Repolyzer!");
+        println!("Performing analysis...");
     }
 
-    if app_args.weekday_stats {
-        print_weekday_stats(&stats);
+    let has_local_location = app_args
+        .locations
+        .iter()
+        .any(|location| matches!(location, GitLocation::Local(_)));
+    if app_args.depth.is_some() && has_local_location {
+        eprintln!("Warning: --depth only applies to remote repositories and is ignored!");
+    }
+    if app_args.timeout.is_some() && has_local_location {
+        eprintln!("Warning: --timeout only applies to remote repositories and is ignored!");
     }
-}
 
-/// Downloads or load the repository depending on the type of location
-fn load_repository(location: &GitLocation) -> Repository {
-    if let GitLocation::Local(path) = location {
-        let repo = Repository::open(path);
-        if repo.is_err() {
-            println!("Could not open the local repository!");
-            exit(2);
-        }
-        repo.unwrap()
-    } else if let GitLocation::Remote(url) = location {
-        let mut temp_dir = std::env::temp_dir();
-        temp_dir.push("repolyzer");
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .expect("Time went backwards!")
-            .as_nanos()
-            .to_string();
-        temp_dir.push(timestamp);
-
-        let repo = Repository::clone(url.as_str(), temp_dir);
-        if repo.is_err() {
-            println!("Failed to clone and open repository!");
-            exit(2);
-        }
-        repo.unwrap()
-    } else {
-        println!("Unknown Git Location!");
-        exit(3);
+    if !app_args.watch {
+        return analyze_and_print(&app_args);
+    }
+    let interval = Duration::from_secs(app_args.watch_interval);
+    let mut refs = watched_refs(&app_args.locations);
+    loop {
+        if !app_args.deterministic && io::stdout().is_terminal() {
+            // Clear the screen, so that only the latest stats are shown
+            print!("\x1b[2J\x1b[H");
+        }
+        // The repository may be in the middle of e.g. a rebase, so the next change is waited for instead of giving up
+        if let Err(e) = analyze_and_print(&app_args) {
+            println!("{}", e);
+        }
+        eprintln!(
+            "Watching for changes every {}s, press Ctrl+C to stop...",
+            app_args.watch_interval
+        );
+        loop {
+            std::thread::sleep(interval);
+            let current = watched_refs(&app_args.locations);
+            if current != refs {
+                refs = current;
+                break;
+            }
+        }
     }
 }
 
-/// Parses the program arguments in order to get the location and other flags.
-fn parse_args() -> AppArgs {
-    let args: Vec<String> = std::env::args().collect();
-    if args.len() < 2 {
-        // No argument was passed (args[0] is the tool itself)
-        println!("{}", HELP);
-        exit(2);
+/// Collects the targets of HEAD and all refs of the local repositories, which change with every new commit, tag or branch.
+/// Repositories that can't be opened at the moment contribute no refs.
+fn watched_refs(locations: &[GitLocation]) -> Vec<(String, Option<git2::Oid>)> {
+    let mut refs = Vec::new();
+    for location in locations {
+        let GitLocation::Local(path) = location else {
+            continue;
+        };
+        let Ok(repository) = Repository::open_ext(
+            path,
+            RepositoryOpenFlags::NO_SEARCH,
+            std::iter::empty::<&std::ffi::OsStr>(),
+        ) else {
+            continue;
+        };
+        let head = repository.head().ok().and_then(|head| head.target());
+        refs.push((format!("{}:HEAD", location), head));
+        let Ok(references) = repository.references() else {
+            continue;
+        };
+        for reference in references.flatten() {
+            let name = reference.name().unwrap_or_default();
+            refs.push((format!("{}:{}", location, name), reference.target()));
+        }
     }
+    refs
+}
 
-    // Create initial AppArgs struct
-    let mut app_args = AppArgs {
-        location: GitLocation::Local(PathBuf::from("")),
-
-        general_overview: true,
-        extended_overview: false,
-        pie_chart: false,
-        commit_graph: false,
-        weekday_stats: false,
+/// Analyzes all repositories once, then prints and writes the stats.
+/// Returns the exit code, which is only non-zero if there were no commits to analyze.
+fn analyze_and_print(app_args: &AppArgs) -> Result<i32, RepolyzerError> {
+    let dump_error = |path: &PathBuf, e: io::Error| {
+        RepolyzerError::Output(format!(
+            "Failed to write the commits to '{}': {}",
+            path.display(),
+            e
+        ))
+    };
+    let commit_dump = match &app_args.dump_commits {
+        Some(path) => Some(Mutex::new(
+            CommitDump::create(path).map_err(|e| dump_error(path, e))?,
+        )),
+        None => None,
     };
 
-    // ----------------- Parse flags
-    for arg in &args {
-        if arg.starts_with('-') {
-            match arg.as_str() {
-                "-c" | "--commit-graph" => app_args.commit_graph = true,
-                "-e" | "--extended-overview" => app_args.extended_overview = true,
-                "-n" | "--no-overview" => app_args.general_overview = false,
-                "-p" | "--pie-chart" => app_args.pie_chart = true,
-                "-w" | "--week-day-stats" => app_args.weekday_stats = true,
-                _ => {
-                    println!("Unknown argument: {}", arg);
-                    println!("{}", HELP);
-                    exit(2);
-                }
-            }
-        }
+    let mut results: Vec<RepositoryResult> = Vec::new();
+    for location in &app_args.locations {
+        results.push(analyze_location(location, app_args, commit_dump.as_ref())?);
+    }
+    if let (Some(path), Some(commit_dump)) = (&app_args.dump_commits, commit_dump) {
+        let commit_dump = commit_dump.into_inner().unwrap();
+        commit_dump.finish().map_err(|e| dump_error(path, e))?;
     }
 
-    // ----------------- Retrieve path from args
-
-    // Filter out any argument that is not the first one and does not start with a '-'
-    let repository_path = args.iter().skip(1).find(|&arg| !arg.starts_with('-'));
-    if let Some(repository_path) = repository_path {
-        if repository_path.starts_with("http") {
-            // Remote HTTP(s) URL
-            let url: Url = Url::parse(repository_path).expect("Could not detect valid URL");
-            app_args.location = GitLocation::Remote(url);
-        } else if repository_path.starts_with("git@") {
-            // Remote SSH URL
-            println!("The provided path seems to be using SSH, which is not supported yet!");
-            exit(2);
-        } else {
-            // Assume a local path then
-            let local_path: PathBuf = PathBuf::from(repository_path);
-            if !local_path.exists() || !local_path.is_dir() {
-                println!("The provided path either does not exist, or is not a directory!");
-                exit(2);
-            }
-            app_args.location = GitLocation::Local(local_path);
+    if app_args.output_dir.is_some() {
+        // All formats are printed from the same stats, so the repositories are only analyzed once
+        for (format, path) in export_paths(app_args) {
+            let export_args = AppArgs {
+                format,
+                output: Some(path),
+                ..app_args.clone()
+            };
+            write_report(&results, &export_args)?;
         }
     } else {
-        println!("No path provided!");
-        exit(2);
+        write_report(&results, app_args)?;
     }
 
-    app_args
-}
+    if let Some(path) = &app_args.csv {
+        // Multiple repositories are combined into a single series
+        let summary;
+        let stats = if let [result] = results.as_slice() {
+            &result.stats
+        } else {
+            summary = aggregate_stats(results.iter().map(|result| &result.stats));
+            &summary
+        };
+        write_daily_csv(path, stats).map_err(|e| {
+            RepolyzerError::Output(format!(
+                "Failed to write the CSV file '{}': {}",
+                path.display(),
+                e
+            ))
+        })?;
+    }
 
-fn gather_stats(repository: Repository, app_args: &AppArgs) -> RepositoryStats {
-    let mut diff_options = git2::DiffOptions::new();
-    diff_options.include_unmodified(false);
-    diff_options.include_untracked(false);
-    diff_options.ignore_submodules(true);
-    diff_options.ignore_blank_lines(true);
+    if results.iter().all(|result| result.stats.commit_count == 0) {
+        return Ok(EXIT_NO_COMMITS);
+    }
+    Ok(0)
+}
 
-    let current_time = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .expect("Time went backwards")
-        .as_secs();
+/// Prints the report into the output file (or stdout) of the arguments
+fn write_report(results: &[RepositoryResult], app_args: &AppArgs) -> Result<(), RepolyzerError> {
+    let output_error = |e: io::Error| {
+        let path = app_args
+            .output
+            .as_deref()
+            .unwrap_or(std::path::Path::new("stdout"));
+        RepolyzerError::Output(format!(
+            "Failed to write the stats to '{}': {}",
+            path.display(),
+            e
+        ))
+    };
+    let mut out = open_output(app_args).map_err(output_error)?;
+    print_report(&mut out, results, app_args).map_err(output_error)?;
+    out.flush().map_err(output_error)
+}
 
-    let mut stats = RepositoryStats {
-        commit_count: 0,
-        last_commit: 0,
-        contributors: HashMap::new(),
+/// Gets the file of every format exported into --output-dir
+fn export_paths(app_args: &AppArgs) -> Vec<(OutputFormat, PathBuf)> {
+    let Some(output_dir) = &app_args.output_dir else {
+        return Vec::new();
+    };
+    app_args
+        .exports
+        .iter()
+        .map(|format| {
+            let file_name = match format {
+                OutputFormat::Text => "report.txt",
+                OutputFormat::Markdown => "report.md",
+                OutputFormat::Json => "stats.json",
+                OutputFormat::Html => "report.html",
+            };
+            (*format, output_dir.join(file_name))
+        })
+        .collect()
+}
 
-        total_files_changes: 0,
-        total_lines_inserted: 0,
-        total_lines_removed: 0,
+/// Writes the commits per day of the commit graph as CSV, with a header and ISO dates
+fn write_daily_csv(path: &std::path::Path, stats: &RepositoryStats) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut csv = BufWriter::new(File::create(path)?);
+    writeln!(csv, "date,commit_count")?;
+    let first_day = stats.today() - chrono::Duration::days(DAYS_IN_GRAPH as i64 - 1);
+    for (day_index, commits) in stats.commits_per_day_last_year.iter().enumerate() {
+        let date = first_day + chrono::Duration::days(day_index as i64);
+        writeln!(csv, "{},{}", date.format("%Y-%m-%d"), commits)?;
+    }
+    csv.flush()
+}
 
-        commits_last_year: 0,
-        longest_commit_streak: 0,
-        current_commit_streak: 0,
-        max_commits_a_day: 0,
-        commits_per_day_last_year: [0; 365],
+/// JSON Lines file that every analyzed commit is written to, while the repositories are analyzed
+struct CommitDump {
+    writer: BufWriter<File>,
+    // The first error while writing, as the commits are written from within the analysis
+    error: Option<io::Error>,
+}
 
-        commits_per_weekday: [0; 7],
-    };
+impl CommitDump {
+    fn create(path: &std::path::Path) -> io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        Ok(CommitDump {
+            writer: BufWriter::new(File::create(path)?),
+            error: None,
+        })
+    }
 
-    let mut prev_commit_time: u64 = 0;
-    let mut current_streak: usize = 0;
+    /// Writes the commit as a single line, together with the repository it belongs to
+    fn write(&mut self, location: &GitLocation, record: &CommitRecord) {
+        if self.error.is_some() {
+            return;
+        }
+        let mut line = serde_json::to_value(record).expect("Failed to serialize commit");
+        line["repository"] = location.to_string().into();
+        if let Err(e) = writeln!(self.writer, "{}", line) {
+            self.error = Some(e);
+        }
+    }
 
-    let mut revwalk = repository.revwalk().expect("Failed to get 'revwalk'");
-    revwalk.push_head().expect("Failed to push HEAD!");
+    fn finish(mut self) -> io::Result<()> {
+        match self.error {
+            Some(e) => Err(e),
+            None => self.writer.flush(),
+        }
+    }
+}
 
-    // Loop over all commit_ids with the help of revwalk
-    for commit_id in revwalk {
-        let commit_id = commit_id.expect("Failed to get commit ID");
-        let commit = repository
-            .find_commit(commit_id)
-            .expect("Could not find commit");
+/// The stats of a single analyzed repository
+struct RepositoryResult<'a> {
+    location: &'a GitLocation,
+    stats: RepositoryStats,
+    has_no_commits: bool,
+    /// Only analyzed with --recurse-submodules
+    submodules: Vec<SubmoduleResult>,
+}
 
-        // A commit was found
-        stats.commit_count += 1;
+impl RepositoryResult<'_> {
+    /// Iterates over the stats of the repository itself and of all its submodules
+    fn all_stats(&self) -> impl Iterator<Item = &RepositoryStats> {
+        std::iter::once(&self.stats).chain(self.submodules.iter().map(|submodule| &submodule.stats))
+    }
+}
 
-        // Add contributor to hashmap and update commit amount
-        {
-            let author = commit.author();
-            let author = author.name();
-            let author = if let Some(author) = author {
-                author
-            } else {
-                UNKNOWN_AUTHOR
-            };
+/// The stats of an initialized submodule
+struct SubmoduleResult {
+    /// Path of the submodule relative to the top-level repository, e.g. "vendor/lib/nested"
+    path: String,
+    stats: RepositoryStats,
+}
 
-            stats.contributors.entry(author.to_string()).or_insert(0);
-            stats.contributors.insert(
-                author.to_string(),
-                stats.contributors.get(&author.to_string()).unwrap() + 1,
+/// Loads and analyzes a single repository, cleaning up temporary clones afterwards
+fn analyze_location<'a>(
+    location: &'a GitLocation,
+    app_args: &AppArgs,
+    commit_dump: Option<&Mutex<CommitDump>>,
+) -> Result<RepositoryResult<'a>, RepolyzerError> {
+    let (repository, clone_dir) = load_repository(location, app_args)?;
+    if app_args.format == OutputFormat::Text {
+        if repository.is_shallow() {
+            println!(
+                "Note: {} is a shallow clone, so the stats only reflect the cloned history!",
+                location
             );
         }
-
-        let commit_time = commit.time().seconds() as u64;
-        if stats.last_commit < commit_time {
-            stats.last_commit = commit_time;
+        if app_args.analysis.repo_size && clone_dir.is_some() {
+            println!(
+                "Note: The size of {} is measured on its fresh clone, which may be smaller than the remote",
+                location
+            );
         }
-
-        // Collect stats for extended overview
-        if app_args.extended_overview {
-            // TODO: Optimize or use multithreading for this
-            let parent = commit.parent(0);
-            if parent.is_err() {
-                // This is the first commit, so there is no parent
-                continue;
-            }
-            let diff = repository
-                .diff_tree_to_tree(
-                    Some(&parent.unwrap().tree().unwrap()),
-                    //Some(&p_tree.as_ref().unwrap()),
-                    Some(&commit.tree().unwrap()),
-                    None,
-                )
-                .expect("Failed to get diff");
-            let diff_stats = diff.stats().expect("Failed to get stats");
-
-            stats.total_files_changes += diff.deltas().count();
-            stats.total_lines_inserted += diff_stats.insertions();
-            stats.total_lines_removed += diff_stats.deletions();
+        if repository.is_bare() {
+            println!(
+                "Note: {} is a bare repository, so its HEAD is analyzed without a working tree",
+                location
+            );
         }
+    }
 
-        if app_args.commit_graph {
-            // Gather commits per day
-            if commit_time > current_time - SECONDS_PER_YEAR {
-                // Commit was made in the last year
-                let day_of_year = (commit_time / SECONDS_PER_DAY) % 365;
-                stats.commits_per_day_last_year[day_of_year as usize] += 1;
-            }
-
-            // Check if the current commit was made within the last 24 hours of the previous commit
-            if prev_commit_time != 0 && commit_time > prev_commit_time - SECONDS_PER_DAY {
-                current_streak += 1;
-            } else {
-                if current_streak > stats.longest_commit_streak {
-                    stats.current_commit_streak = current_streak;
+    let has_no_commits = repository.is_empty().unwrap_or(false);
+    // Cached stats don't contain the single commits to dump, and the size on disk changes without new commits
+    let cache_key = if app_args.no_cache || commit_dump.is_some() || app_args.analysis.repo_size {
+        None
+    } else {
+        cache_key(&repository, location, &app_args.analysis)
+    };
+    let stats = match cache_key.as_deref().and_then(load_cached_stats) {
+        // The stats may have been cached earlier today, so they keep the time they were analyzed at,
+        // which the quarters of the trend and the active contributors were counted against
+        Some(stats) => Ok(stats),
+        None => {
+            let on_commit = |record: &CommitRecord| {
+                if let Some(commit_dump) = commit_dump {
+                    commit_dump.lock().unwrap().write(location, record);
                 }
-                current_streak = 0;
-                prev_commit_time = commit_time;
+            };
+            let stats = analyze_with_commits(
+                &repository,
+                &app_args.analysis,
+                commit_dump.map(|_| &on_commit as &(dyn Fn(&CommitRecord) + Sync)),
+            );
+            if let (Some(cache_key), Ok(stats)) = (&cache_key, &stats) {
+                store_cached_stats(cache_key, stats);
             }
+            stats
         }
+    };
 
-        if app_args.weekday_stats {
-            // Gather commits per weekday
-            let weekday = DT::from_timestamp(commit_time as i64, 0).unwrap().weekday();
-            stats.commits_per_weekday[weekday.num_days_from_monday() as usize] += 1;
-        }
+    let mut submodules = Vec::new();
+    if app_args.recurse_submodules {
+        analyze_submodules(&repository, app_args, "", &mut submodules)?;
     }
 
-    if app_args.commit_graph {
-        // Calculate max commits a day
-        stats.max_commits_a_day = *stats.commits_per_day_last_year.iter().max().unwrap();
+    // Clean up data
+    temp_dir_cleanup(repository, clone_dir);
 
-        // Calculate commits in the last year
-        for i in 0..365 {
-            stats.commits_last_year += stats.commits_per_day_last_year[i];
-        }
+    Ok(RepositoryResult {
+        location,
+        stats: stats?,
+        has_no_commits,
+        submodules,
+    })
+}
 
-        // Calculate longest streak
-        current_streak = 0;
-        for i in 0..365 {
-            if stats.commits_per_day_last_year[i] > 0 {
-                current_streak += 1;
-            } else {
-                if current_streak > stats.longest_commit_streak {
-                    stats.longest_commit_streak = current_streak;
-                }
-                current_streak = 0;
+/// Analyzes the submodules of the repository and their submodules in turn.
+/// Submodules that aren't initialized have no repository to open, so they are skipped.
+fn analyze_submodules(
+    repository: &Repository,
+    app_args: &AppArgs,
+    prefix: &str,
+    results: &mut Vec<SubmoduleResult>,
+) -> Result<(), RepolyzerError> {
+    let Ok(submodules) = repository.submodules() else {
+        return Ok(());
+    };
+    // The refs of the top-level repository don't exist in the submodules, so their HEAD is analyzed
+    let opts = AnalysisOptions {
+        reference: None,
+        compare: None,
+        range: None,
+        ..app_args.analysis.clone()
+    };
+    for submodule in submodules {
+        let path = format!("{}{}", prefix, submodule.path().display());
+        let Ok(submodule_repository) = submodule.open() else {
+            if app_args.format == OutputFormat::Text {
+                println!(
+                    "Note: Skipping the submodule {}, as it is not initialized",
+                    path
+                );
             }
-        }
+            continue;
+        };
+        let stats = analyze_with_commits(&submodule_repository, &opts, None)?;
+        results.push(SubmoduleResult {
+            path: path.clone(),
+            stats,
+        });
+        analyze_submodules(
+            &submodule_repository,
+            app_args,
+            &format!("{}/", path),
+            results,
+        )?;
     }
-
-    // Clean up data
-    temp_dir_cleanup(repository, &app_args.location);
-
-    stats
+    Ok(())
 }
 
-fn print_general_overview(stats: &RepositoryStats) {
-    let dt = DT::from_timestamp(stats.last_commit as i64, 0).unwrap();
-
-    println!("-------------------------------------");
-    println!("Overall commit stats:");
-    println!("Commit amount ......... {}", stats.commit_count);
-    println!("Last commit ........... {}", dt.format("%d-%m-%Y %H:%M:%S"));
-    println!("Contributor amount .... {}", stats.contributors.len());
-    println!("-------------------------------------");
-}
-
-fn print_extended_overview(stats: &RepositoryStats) {
-    let dt = DT::from_timestamp(stats.last_commit as i64, 0).unwrap();
-    println!("-------------------------------------");
-    println!("Overall commit stats:");
-    println!("Commit amount ......... {}", stats.commit_count);
-    println!("Last commit ........... {}", dt.format("%d-%m-%Y %H:%M:%S"));
-    println!("Contributor amount .... {}", stats.contributors.len());
-    println!("Files changed ......... {}", stats.total_files_changes);
-    println!("Lines inserted......... {}", stats.total_lines_inserted);
-    println!("Lines removed ......... {}", stats.total_lines_removed);
-    println!(
-        "Total lines (delta) ... {}",
-        stats.total_lines_inserted - stats.total_lines_removed
-    );
-    println!(
-        "Add./Del. ratio........ {:.2}",
-        stats.total_lines_inserted as f64 / stats.total_lines_removed as f64
+/// Builds the key under which the stats are cached, from everything that influences them.
+/// Returns `None` if there is nothing to cache, e.g. as the repository has no commits.
+fn cache_key(
+    repository: &Repository,
+    location: &GitLocation,
+    opts: &AnalysisOptions,
+) -> Option<String> {
+    let start_commit = match (&opts.compare, &opts.range) {
+        // Both ends of the range may move
+        (Some(range), _) => {
+            let (base, head) = resolve_range(repository, range).ok()?;
+            format!("{}..{}", base, head)
+        }
+        (_, Some(spec)) => {
+            let (start_commits, hidden_commits) = resolve_revisions(repository, spec).ok()?;
+            format!("{:?}..{:?}", hidden_commits, start_commits)
+        }
+        _ => repository
+            .revparse_single(opts.reference.as_deref().unwrap_or("HEAD"))
+            .and_then(|object| object.peel_to_commit())
+            .ok()?
+            .id()
+            .to_string(),
+    };
+    let location = match location {
+        GitLocation::Local(path) => path.canonicalize().ok()?.display().to_string(),
+        remote => remote.to_string(),
+    };
+    let today = DT::from_timestamp(opts.now(), 0)
+        .unwrap_or_default()
+        .with_timezone(&Local)
+        .date_naive();
+    // The progress counter doesn't change the stats
+    let opts = AnalysisOptions {
+        progress: false,
+        live_stats: false,
+        ..opts.clone()
+    };
+
+    // The trend of the overview and the commit graph end today
+    let mut key = format!(
+        "{}\n{}\n{}\n{:?}\n{}",
+        env!("CARGO_PKG_VERSION"),
+        location,
+        start_commit,
+        opts,
+        today
     );
-    println!("-------------------------------------");
+    if opts.tag_stats || opts.branch_stats {
+        // Tags and branches may change without HEAD changing
+        for reference in repository.references().ok()?.flatten() {
+            key.push_str(&format!(
+                "\n{}={}",
+                reference.name().unwrap_or(""),
+                reference
+                    .target()
+                    .map(|oid| oid.to_string())
+                    .unwrap_or_default()
+            ));
+        }
+    }
+    Some(key)
 }
 
-fn print_pie_chart(stats: &RepositoryStats) {
-    const NAMED_COMMITS_IN_CHART: usize = 5;
-    const SYMBOLS: [char; 6] = ['•', '▪', '▴', '◆', '⬟', '◆'];
-    println!("Commit pie chart:");
-
-    let colors = [
-        Color::RGB(255, 99, 132),  // Red
-        Color::RGB(54, 162, 235),  // Blue
-        Color::RGB(255, 206, 86),  // Yellow
-        Color::RGB(75, 192, 192),  // Teal
-        Color::RGB(153, 102, 255), // Purple
-        Color::RGB(255, 159, 64),  // Orange
-    ];
+/// Path of the cache file for the key in $XDG_CACHE_HOME/repolyzer (~/.cache/repolyzer)
+fn cache_file(cache_key: &str) -> Option<PathBuf> {
+    let cache_dir = std::env::var_os("XDG_CACHE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?;
+    let mut hasher = DefaultHasher::new();
+    cache_key.hash(&mut hasher);
+    Some(
+        cache_dir
+            .join("repolyzer")
+            .join(format!("{:016x}.json", hasher.finish())),
+    )
+}
 
-    // Sort descending by commit amount
-    let mut top_contributors: Vec<(&String, &u64)> = stats.contributors.iter().collect();
-    top_contributors.sort_by(|a, b| b.1.cmp(a.1));
-    top_contributors.truncate(NAMED_COMMITS_IN_CHART);
-
-    // Add "Others" if there are more than NAMED_COMMITS_IN_CHART contributors
-    let mut others = 0;
-    for (_, commits) in stats.contributors.iter().skip(NAMED_COMMITS_IN_CHART) {
-        others += commits;
-    }
-    let others_contributors = (&"Others".to_string(), &others);
-    if others > 0 {
-        top_contributors.push(others_contributors);
-    }
-
-    // Create data vector
-    let mut top_data: Vec<Data> = Vec::new();
-    for i in 0..top_contributors.len() {
-        let (name, commits) = top_contributors[i];
-        let data = Data {
-            label: name.to_string(),
-            value: *commits as f32,
-            color: Some(colors[i].into()),
-            fill: SYMBOLS[i % SYMBOLS.len()],
-        };
-        top_data.push(data);
+/// Loads the cached stats, if there are any for exactly this key
+fn load_cached_stats(cache_key: &str) -> Option<RepositoryStats> {
+    let content = std::fs::read_to_string(cache_file(cache_key)?).ok()?;
+    let mut cached: serde_json::Value = serde_json::from_str(&content).ok()?;
+    // Guard against hash collisions
+    if cached["key"] != cache_key {
+        return None;
     }
+    serde_json::from_value(cached["stats"].take()).ok()
+}
 
-    // Create chart
-    Chart::new()
-        .radius(9)
-        .aspect_ratio(3)
-        .legend(true)
-        .draw(&top_data);
+/// Stores the stats in the cache. Failing to do so is not fatal, as the cache is only an optimization.
+fn store_cached_stats(cache_key: &str, stats: &RepositoryStats) {
+    let Some(path) = cache_file(cache_key) else {
+        return;
+    };
+    let cached = serde_json::json!({ "key": cache_key, "stats": stats });
+    let result = path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|_| std::fs::write(&path, cached.to_string()));
+    if let Err(e) = result {
+        eprintln!(
+            "Warning: Failed to write the cache file '{}': {}",
+            path.display(),
+            e
+        );
+    }
 }
 
-fn print_commit_checker_board(stats: &RepositoryStats) {
-    let distribution = calculate_symbol_distribution(stats);
+/// Prints the stats of all repositories, followed by a summary if there are multiple
+fn print_report(
+    out: &mut dyn Write,
+    results: &[RepositoryResult],
+    app_args: &AppArgs,
+) -> io::Result<()> {
+    if app_args.format == OutputFormat::Html {
+        return html::print_report(out, results, app_args);
+    }
+    if let [result] = results {
+        if app_args.format == OutputFormat::Json {
+            return writeln!(out, "{}", serialize_result(result));
+        }
+        print_stats(out, &result.stats, app_args, result.has_no_commits)?;
+        return print_submodules(out, result, app_args);
+    }
 
-    println!("╔═══════════════════════════════════════════════════════════════════════════════════════════════════════════════");
-    println!("║\tCommits in the last year: {} | Longest Streak: {} days | Current Streak: {} days | Max a day: {}"
-        , stats.commits_last_year, stats.longest_commit_streak, stats.current_commit_streak, stats.max_commits_a_day);
-    println!("╠═══════════════════════════════════════════════════════════════════════════════════════════════════════════════");
-    println!("║      Jan      Feb      Mar      Apr      May      Jun      Jul      Aug      Sep      Oct      Nov     Dec");
-    println!(
-        "║ Mon\t{}",
-        calculate_day_commit_graph(stats, chrono::Weekday::Mon, &distribution)
-    );
-    println!(
-        "║ Tue\t{}",
-        calculate_day_commit_graph(stats, chrono::Weekday::Tue, &distribution)
-    );
-    println!(
-        "║ Wed\t{}",
-        calculate_day_commit_graph(stats, chrono::Weekday::Wed, &distribution)
-    );
-    println!(
-        "║ Thu\t{}",
-        calculate_day_commit_graph(stats, chrono::Weekday::Thu, &distribution)
-    );
-    println!(
-        "║ Fri\t{}",
-        calculate_day_commit_graph(stats, chrono::Weekday::Fri, &distribution)
-    );
-    println!(
-        "║ Sat\t{}",
-        calculate_day_commit_graph(stats, chrono::Weekday::Sat, &distribution)
-    );
-    println!(
-        "║ Sun\t{}",
-        calculate_day_commit_graph(stats, chrono::Weekday::Sun, &distribution)
-    );
-    println!("╚═══════════════════════════════════════════════════════════════════════════════════════════════════════════════");
-}
+    if app_args.format == OutputFormat::Json {
+        let repositories: Vec<serde_json::Value> = results
+            .iter()
+            .map(|result| {
+                let mut json = serialize_result(result);
+                json["repository"] = result.location.to_string().into();
+                json
+            })
+            .collect();
+        return writeln!(out, "{}", serde_json::Value::from(repositories));
+    }
 
-fn print_weekday_stats(stats: &RepositoryStats) {
-    // Limit to 20 bars per weekday
-    let max_commits = stats.commits_per_weekday.iter().max().unwrap();
+    let markdown = app_args.format == OutputFormat::Markdown;
+    for result in results {
+        if markdown {
+            writeln!(out, "## Repository: {}", result.location)?;
+            writeln!(out)?;
+        } else {
+            writeln!(out, "=====================================")?;
+            writeln!(out, "Repository: {}", result.location)?;
+        }
+        print_stats(out, &result.stats, app_args, result.has_no_commits)?;
+        print_submodules(out, result, app_args)?;
+    }
 
-    println!("-------------------------------------");
-    println!("Commits per weekday:");
-    for i in 0..7 {
-        let percentage =
-            (stats.commits_per_weekday[i] as f64 / *max_commits as f64 * 20.0) as usize;
-        let weekday = match i {
-            0 => "Mon",
-            1 => "Tue",
-            2 => "Wed",
-            3 => "Thu",
-            4 => "Fri",
-            5 => "Sat",
-            6 => "Sun",
-            _ => "???", // Should/Can never happen
-        };
-        println!(
-            "\t{}\t{}\t|{}",
-            weekday,
-            stats.commits_per_weekday[i],
-            "█".repeat(percentage)
-        );
+    let summary = aggregate_stats(results.iter().flat_map(RepositoryResult::all_stats));
+    let extended = app_args.analysis.extended_stats;
+    let date_format = &app_args.date_format;
+    if markdown {
+        writeln!(out, "## Summary of all {} repositories", results.len())?;
+        writeln!(out)?;
+        print_markdown_overview(out, &summary, extended, date_format)
+    } else {
+        writeln!(out, "=====================================")?;
+        writeln!(out, "Summary of all {} repositories:", results.len())?;
+        print_overview(out, &summary, extended, date_format)
     }
 }
 
-/// Calculates the distribution borders for the commit checker board
-fn calculate_symbol_distribution(stats: &RepositoryStats) -> [usize; CHECKERBOARD_SYMBOL_AMOUNT] {
-    // Get the max commits a day
-    let mut max_commits_a_day = 0;
-    for commits in stats.commits_per_day_last_year.iter() {
-        if *commits > max_commits_a_day {
-            max_commits_a_day = *commits;
-        }
+/// Prints the overview of every analyzed submodule, followed by the totals of the repository including them
+fn print_submodules(
+    out: &mut dyn Write,
+    result: &RepositoryResult,
+    app_args: &AppArgs,
+) -> io::Result<()> {
+    if result.submodules.is_empty() {
+        return Ok(());
     }
+    let extended = app_args.analysis.extended_stats;
+    let date_format = &app_args.date_format;
+    let heading = |out: &mut dyn Write, title: &str| match app_args.format {
+        OutputFormat::Markdown => writeln!(out, "## {}\n", title),
+        OutputFormat::Html => writeln!(out, "<h2>{}</h2>", html::escape_html(title)),
+        _ => writeln!(out, "=====================================\n{}:", title),
+    };
+    let overview = |out: &mut dyn Write, stats: &RepositoryStats| match app_args.format {
+        OutputFormat::Markdown => print_markdown_overview(out, stats, extended, date_format),
+        OutputFormat::Html => html::print_overview(out, stats, extended, date_format),
+        _ => print_overview(out, stats, extended, date_format),
+    };
 
-    // Calculate the symbol distribution borders
-    let range_size = max_commits_a_day / CHECKERBOARD_SYMBOL_AMOUNT;
-    let low = range_size;
-    let more = range_size * 2;
-    let even_more = range_size * 3;
-    let a_lot = range_size * 4;
+    for submodule in &result.submodules {
+        heading(out, &format!("Submodule {}", submodule.path))?;
+        overview(out, &submodule.stats)?;
+    }
+    heading(
+        out,
+        &format!(
+            "Total including {} submodule{}",
+            result.submodules.len(),
+            if result.submodules.len() == 1 {
+                ""
+            } else {
+                "s"
+            }
+        ),
+    )?;
+    overview(out, &aggregate_stats(result.all_stats()))
+}
 
-    let distribution = [0, low, more, even_more, a_lot];
+/// Combines the commits, contributors and churn of multiple repositories
+fn aggregate_stats<'a>(all_stats: impl Iterator<Item = &'a RepositoryStats>) -> RepositoryStats {
+    let mut summary = RepositoryStats {
+        is_combined: true,
+        ..RepositoryStats::default()
+    };
+    for stats in all_stats {
+        summary.author_filter.clone_from(&stats.author_filter);
+        summary.excluded_authors.clone_from(&stats.excluded_authors);
+        summary.path_filter.clone_from(&stats.path_filter);
+        summary.merges_excluded = stats.merges_excluded;
+        summary.contributor_identity = stats.contributor_identity;
+        summary.first_parent_only = stats.first_parent_only;
+        summary.commit_limit = stats.commit_limit;
+        summary.has_extended_stats = stats.has_extended_stats;
+        summary.diff_algorithm = stats.diff_algorithm;
+        summary.analyzed_at = stats.analyzed_at;
 
-    // Print distribution
-    println!("-------------------------------------");
-    print!("Distribution: ");
-    print!("{} = {} | ", SYMBOLS[0], distribution[0]);
-    for i in 1..distribution.len() - 1 {
-        print!("{} for <= {}, ", SYMBOLS[i], distribution[i]);
+        if stats.commit_count > 0 {
+            if summary.commit_count == 0 || stats.first_commit < summary.first_commit {
+                summary.first_commit = stats.first_commit;
+            }
+            summary.last_commit = summary.last_commit.max(stats.last_commit);
+        }
+        summary.commit_count += stats.commit_count;
+        summary.merge_commits += stats.merge_commits;
+        summary.empty_commits += stats.empty_commits;
+        summary.empty_excluded |= stats.empty_excluded;
+        summary.commits_last_quarter += stats.commits_last_quarter;
+        summary.commits_prior_quarter += stats.commits_prior_quarter;
+        for (author, commits) in &stats.contributors {
+            *summary.contributors.entry(author.clone()).or_insert(0.0) += commits;
+        }
+
+        summary.total_files_changes += stats.total_files_changes;
+        summary.binary_files_changed += stats.binary_files_changed;
+        if let Some((sampled, diffable)) = stats.churn_sample {
+            let churn_sample = summary.churn_sample.get_or_insert((0, 0));
+            churn_sample.0 += sampled;
+            churn_sample.1 += diffable;
+        }
+        summary.total_lines_inserted += stats.total_lines_inserted;
+        summary.total_lines_removed += stats.total_lines_removed;
+        for (author, (inserted, removed)) in &stats.lines_per_author {
+            let lines = summary
+                .lines_per_author
+                .entry(author.clone())
+                .or_insert((0, 0));
+            lines.0 += inserted;
+            lines.1 += removed;
+        }
+
+        for (day, commits) in stats.commits_per_day_last_year.iter().enumerate() {
+            summary.commits_per_day_last_year[day] += commits;
+        }
     }
-    println!(
-        "{} for > {}",
-        SYMBOLS[distribution.len() - 1],
-        distribution[distribution.len() - 1]
-    );
-    println!();
+    summary
+}
 
-    distribution
+/// Opens the file passed to --output (creating missing directories), or stdout otherwise
+fn open_output(app_args: &AppArgs) -> io::Result<Box<dyn Write>> {
+    if let Some(path) = &app_args.output {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        Ok(Box::new(BufWriter::new(File::create(path)?)))
+    } else {
+        Ok(Box::new(io::stdout()))
+    }
 }
 
-fn calculate_day_commit_graph(
+/// Prints all enabled stats
+fn print_stats(
+    out: &mut dyn Write,
     stats: &RepositoryStats,
-    weekday: chrono::Weekday,
-    symbol_dist: &[usize; CHECKERBOARD_SYMBOL_AMOUNT],
-) -> String {
-    let today = Local::now();
+    app_args: &AppArgs,
+    has_no_commits: bool,
+) -> io::Result<()> {
+    let markdown = app_args.format == OutputFormat::Markdown;
+    let html = app_args.format == OutputFormat::Html;
+    // Sections without a dedicated Markdown or HTML representation are put into code blocks
+    let section = |out: &mut dyn Write,
+                   printer: &dyn Fn(&mut dyn Write) -> io::Result<()>|
+     -> io::Result<()> {
+        if markdown {
+            print_markdown_code_block(out, printer)
+        } else if html {
+            html::print_pre_section(out, printer)
+        } else {
+            printer(out)
+        }
+    };
 
-    let mut num_of_weekdays = 0;
-    for i in 0..365 {
-        let day = today - chrono::Duration::days(i);
-        if day.weekday() == weekday {
-            num_of_weekdays += 1;
+    if has_no_commits {
+        if markdown {
+            return writeln!(out, "_Repository has no commits_");
         }
+        if html {
+            return writeln!(out, "<p>Repository has no commits</p>");
+        }
+        return writeln!(out, "Repository has no commits");
     }
 
-    let mut graph_line = String::new();
-    for i in 0..num_of_weekdays {
-        let day_index = 7 * i + weekday.num_days_from_monday() as usize;
-        if day_index >= 365 {
-            break;
+    let extended = app_args.analysis.extended_stats;
+    let date_format = &app_args.date_format;
+    let width = output_width(app_args);
+    if app_args.general_overview || extended {
+        if markdown {
+            print_markdown_overview(out, stats, extended, date_format)?;
+        } else if html {
+            html::print_overview(out, stats, extended, date_format)?;
+        } else {
+            print_overview(out, stats, extended, date_format)?;
         }
+    }
 
-        let commits_on_day = stats.commits_per_day_last_year[day_index];
+    if extended {
+        if markdown {
+            print_markdown_lines_per_author(out, stats)?;
+        } else if html {
+            section(out, &|out| print_lines_per_author(out, stats))?;
+        } else {
+            print_lines_per_author(out, stats)?;
+        }
+    }
 
-        // Get symbol for this day
-        let mut symbol = ' ';
-        for j in 0..symbol_dist.len() {
-            if commits_on_day <= symbol_dist[j] {
-                symbol = SYMBOLS[j];
-                break;
-            }
+    if app_args.contributors {
+        if markdown {
+            print_markdown_contributors(out, stats, app_args.top, app_args.min_commits)?;
+        } else if html {
+            section(out, &|out| {
+                print_contributors(out, stats, app_args.top, app_args.min_commits)
+            })?;
+        } else {
+            print_contributors(out, stats, app_args.top, app_args.min_commits)?;
+        }
+    }
+
+    if app_args.pie_chart {
+        if html {
+            html::print_pie_chart(out, stats, app_args.top, app_args.min_commits)?;
+        } else {
+            // Colors can't be pasted, so Markdown always gets the plain text variant
+            let ascii = app_args.ascii || markdown;
+            section(out, &|out| {
+                print_pie_chart(out, stats, app_args.top, app_args.min_commits, ascii)
+            })?;
         }
-        if symbol == ' ' {
-            // If no symbol was found, use the last one (as it then is > symbol_dist[CHECKERBOARD_SYMBOL_AMOUNT - 1])
-            symbol = SYMBOLS[SYMBOLS.len() - 1];
+    }
+
+    if app_args.analysis.commit_graph {
+        if html {
+            html::print_commit_graph(out, stats)?;
+        } else {
+            section(out, &|out| {
+                print_commit_checker_board(out, stats, &app_args.theme.symbols(), width)
+            })?;
         }
+    }
 
-        graph_line.push(' ');
-        graph_line.push(symbol);
+    if app_args.analysis.all_time_heatmap {
+        if html {
+            html::print_all_time_heatmap(out, stats)?;
+        } else {
+            section(out, &|out| {
+                print_all_time_heatmap(out, stats, &app_args.theme.symbols(), width)
+            })?;
+        }
     }
-    graph_line
-}
 
-/// Cleans up the temporary directory if the repository was cloned
-fn temp_dir_cleanup(repository: Repository, location: &GitLocation) {
-    if let GitLocation::Remote(_) = location {
-        let path = repository.path().parent().unwrap();
-        std::fs::remove_dir_all(path).expect("Failed to remove temporary directory!");
+    if app_args.analysis.weekday_stats {
+        section(out, &|out| {
+            print_weekday_stats(out, stats, app_args.normalize_weekdays, width)
+        })?;
+    }
+
+    if app_args.analysis.hour_stats {
+        section(out, &|out| print_hour_stats(out, stats, width))?;
     }
-}
\ No newline at end of file
+
+    if app_args.analysis.message_stats {
+        section(out, &|out| print_message_stats(out, stats))?;
+    }
+
+    if app_args.analysis.signature_stats {
+        section(out, &|out| print_signature_stats(out, stats))?;
+    }
+
+    if app_args.analysis.lang_stats {
+        section(out, &|out| print_lang_stats(out, stats))?;
+    }
+
+    if app_args.analysis.dir_stats {
+        section(out, &|out| print_dir_stats(out, stats))?;
+    }
+
+    if app_args.analysis.size_histogram {
+        section(out, &|out| print_size_histogram(out, stats, width))?;
+    }
+
+    if app_args.analysis.hotspots {
+        section(out, &|out| print_hotspots(out, stats))?;
+    }
+
+    if app_args.analysis.coupling.is_some() {
+        section(out, &|out| print_coupling(out, stats))?;
+    }
+
+    if app_args.analysis.tag_stats {
+        section(out, &|out| print_tag_stats(out, stats, date_format))?;
+    }
+
+    if app_args.analysis.branch_stats {
+        section(out, &|out| print_branch_stats(out, stats, date_format))?;
+    }
+
+    if app_args.analysis.growth_stats {
+        section(out, &|out| print_growth_stats(out, stats))?;
+    }
+
+    if app_args.analysis.author_timeline {
+        section(out, &|out| print_author_timeline(out, stats, date_format))?;
+    }
+
+    if app_args.analysis.contributor_classes.is_some() {
+        section(out, &|out| print_contributor_classes(out, stats))?;
+    }
+
+    if app_args.analysis.largest_files.is_some() {
+        section(out, &|out| print_largest_files(out, stats))?;
+    }
+
+    if app_args.analysis.repo_size {
+        section(out, &|out| print_repository_size(out, stats))?;
+    }
+
+    Ok(())
+}
+
+/// Downloads or load the repository depending on the type of location
+/// Remote repositories are cloned with the `--depth`, `--retries` and `--timeout` of the arguments.
+/// Also returns the temporary directory of remote clones, which has to be removed afterwards.
+fn load_repository(
+    location: &GitLocation,
+    app_args: &AppArgs,
+) -> Result<(Repository, Option<PathBuf>), RepolyzerError> {
+    if let GitLocation::Local(path) = location {
+        // The path has to be the repository itself (a working tree, linked worktree or bare repository),
+        // instead of any directory inside of one
+        let repository = Repository::open_ext(
+            path,
+            RepositoryOpenFlags::NO_SEARCH,
+            std::iter::empty::<&std::ffi::OsStr>(),
+        )
+        .map_err(|_| RepolyzerError::RepoOpen)?;
+        return Ok((repository, None));
+    }
+
+    let attempts = app_args.retries + 1;
+    let mut attempt = 1;
+    loop {
+        let clone_dir = create_temp_dir();
+        let error = match clone_repository(location, app_args, &clone_dir) {
+            Ok(repository) => {
+                if attempt > 1 {
+                    eprintln!("Cloned {} on attempt {} of {}", location, attempt, attempts);
+                }
+                return Ok((repository, Some(clone_dir)));
+            }
+            Err(e) => e,
+        };
+        // Don't leave a partial clone behind, neither for the next attempt nor afterwards
+        remove_clone_dir(&clone_dir);
+
+        // A rejected access token or key won't be accepted on the next attempt either
+        if error.code() == ErrorCode::Auth || attempt == attempts {
+            return Err(clone_error(location, &error, attempts));
+        }
+        let backoff = Duration::from_secs(1 << (attempt - 1).min(5));
+        eprintln!(
+            "Warning: Clone attempt {} of {} failed ({}), retrying in {}s...",
+            attempt,
+            attempts,
+            error.message(),
+            backoff.as_secs()
+        );
+        std::thread::sleep(backoff);
+        attempt += 1;
+    }
+}
+
+/// Makes a single attempt at cloning a remote repository into `clone_dir`
+fn clone_repository(
+    location: &GitLocation,
+    app_args: &AppArgs,
+    clone_dir: &std::path::Path,
+) -> Result<Repository, git2::Error> {
+    let (url, mut callbacks) = match location {
+        GitLocation::Local(_) => unreachable!("Local repositories are opened instead of cloned"),
+        GitLocation::Remote(url) => {
+            let token = TOKEN_ENV_VARS
+                .iter()
+                .filter_map(|name| std::env::var(name).ok())
+                .find(|token| !token.is_empty());
+            (url.as_str(), https_callbacks(token))
+        }
+        GitLocation::RemoteSsh(url) => (url.as_str(), ssh_callbacks()),
+    };
+
+    // libgit2 has no timeout of its own, so the progress callbacks abort the transfer once the time is up
+    let deadline = app_args
+        .timeout
+        .map(|timeout| (Instant::now() + Duration::from_secs(timeout), timeout));
+    if let Some((deadline, _)) = deadline {
+        callbacks.transfer_progress(move |_progress| Instant::now() < deadline);
+        callbacks.sideband_progress(move |_data| Instant::now() < deadline);
+    }
+
+    let mut fetch_options = git2::FetchOptions::new();
+    if let Some(depth) = app_args.depth {
+        fetch_options.depth(depth);
+    }
+    fetch_options.remote_callbacks(callbacks);
+
+    git2::build::RepoBuilder::new()
+        .fetch_options(fetch_options)
+        .clone(url, clone_dir)
+        .map_err(|e| match deadline {
+            Some((deadline, timeout)) if Instant::now() >= deadline => {
+                git2::Error::from_str(&format!("Timed out after {} seconds", timeout))
+            }
+            _ => e,
+        })
+}
+
+/// Describes why cloning a remote repository failed, with a hint for rejected HTTPS credentials
+fn clone_error(location: &GitLocation, error: &git2::Error, attempts: u32) -> RepolyzerError {
+    let via = if matches!(location, GitLocation::RemoteSsh(_)) {
+        " via SSH"
+    } else {
+        ""
+    };
+    let tries = if attempts > 1 && error.code() != ErrorCode::Auth {
+        format!(" (all {} attempts failed)", attempts)
+    } else {
+        String::new()
+    };
+    let hint = if error.code() == ErrorCode::Auth && matches!(location, GitLocation::Remote(_)) {
+        format!(
+            "\nIf the repository is private, set up a git credential helper or provide an access token via the {} environment variable.",
+            TOKEN_ENV_VARS.join(" or ")
+        )
+    } else {
+        String::new()
+    };
+    RepolyzerError::RepoClone(format!(
+        "Failed to clone and open repository{}: {}{}{}",
+        via,
+        error.message(),
+        tries,
+        hint
+    ))
+}
+
+/// Creates the path of a new, unique temporary directory to clone a remote repository into.
+/// The process id and a counter keep concurrent runs and multiple clones of one run apart, even with coarse clocks.
+fn create_temp_dir() -> PathBuf {
+    static CLONE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let mut temp_dir = std::env::temp_dir();
+    temp_dir.push("repolyzer");
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Time went backwards!")
+        .as_nanos();
+    temp_dir.push(format!(
+        "{}-{}-{}",
+        std::process::id(),
+        CLONE_COUNTER.fetch_add(1, Ordering::Relaxed),
+        timestamp
+    ));
+    temp_dir
+}
+
+/// Builds the callbacks used to authenticate SSH clones.
+/// The SSH agent is tried first, falling back to the default key at `~/.ssh/id_ed25519`.
+fn ssh_callbacks<'a>() -> RemoteCallbacks<'a> {
+    let mut attempts = 0;
+    let mut callbacks = RemoteCallbacks::new();
+    callbacks.credentials(move |_url, username_from_url, _allowed_types| {
+        let username = username_from_url.unwrap_or("git");
+        attempts += 1;
+        match attempts {
+            1 => Cred::ssh_key_from_agent(username),
+            2 => {
+                let home = std::env::var_os("HOME")
+                    .or_else(|| std::env::var_os("USERPROFILE"))
+                    .ok_or_else(|| git2::Error::from_str("Could not determine home directory"))?;
+                let key_path = PathBuf::from(home).join(".ssh").join("id_ed25519");
+                Cred::ssh_key(username, None, &key_path, None)
+            }
+            // Stop libgit2 from asking over and over again
+            _ => Err(git2::Error::from_str("No usable SSH credentials found")),
+        }
+    });
+    callbacks
+}
+
+/// Builds the callbacks used to authenticate HTTPS clones.
+/// The credential helper of the git config is asked first, so whatever works for `git clone` works here as well.
+/// If it has no (or rejected) credentials, the access token is used.
+fn https_callbacks<'a>(token: Option<String>) -> RemoteCallbacks<'a> {
+    let mut helper_attempted = false;
+    let mut token_attempted = false;
+    let mut callbacks = RemoteCallbacks::new();
+    callbacks.credentials(move |url, username_from_url, _allowed_types| {
+        if !helper_attempted {
+            helper_attempted = true;
+            // Without a helper or stored credentials, this fails and the token is tried instead
+            let credentials = git2::Config::open_default()
+                .and_then(|config| Cred::credential_helper(&config, url, username_from_url));
+            if credentials.is_ok() {
+                return credentials;
+            }
+        }
+        match &token {
+            Some(token) if !token_attempted => {
+                token_attempted = true;
+                // Hosts like GitHub ignore the username as long as it isn't empty
+                Cred::userpass_plaintext(username_from_url.unwrap_or("x-access-token"), token)
+            }
+            // Stop libgit2 from asking over and over again
+            Some(_) => Err(git2::Error::new(
+                ErrorCode::Auth,
+                git2::ErrorClass::Http,
+                "The access token was rejected",
+            )),
+            None => Err(git2::Error::new(
+                ErrorCode::Auth,
+                git2::ErrorClass::Http,
+                "No credentials were accepted",
+            )),
+        }
+    });
+    callbacks
+}
+
+/// Parses the program arguments in order to get the location and other flags.
+fn parse_args() -> Result<AppArgs, RepolyzerError> {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() < 2 {
+        // No argument was passed (args[0] is the tool itself)
+        return Err(RepolyzerError::NoArgs);
+    }
+
+    // Create initial AppArgs struct, with the configuration file taking precedence over the built-in defaults
+    let mut app_args = AppArgs::new();
+    Config::load()?.apply(&mut app_args);
+
+    // ----------------- Parse flags and retrieve paths from args
+    let mut repository_paths: Vec<String> = Vec::new();
+    let mut args_iter = args.iter().skip(1).peekable();
+    while let Some(arg) = args_iter.next() {
+        if arg.starts_with('-') {
+            match arg.as_str() {
+                "-c" | "--commit-graph" => app_args.analysis.commit_graph = true,
+                "--heatmap-all" => app_args.analysis.all_time_heatmap = true,
+                "-e" | "--extended-overview" => app_args.analysis.extended_stats = true,
+                "-n" | "--no-overview" => app_args.general_overview = false,
+                "-p" | "--pie-chart" => app_args.pie_chart = true,
+                "--contributors" => app_args.contributors = true,
+                "-w" | "--week-day-stats" => app_args.analysis.weekday_stats = true,
+                "--normalize-weekdays" => app_args.normalize_weekdays = true,
+                "--hour-stats" => app_args.analysis.hour_stats = true,
+                "--message-stats" => app_args.analysis.message_stats = true,
+                "--signatures" => app_args.analysis.signature_stats = true,
+                "--lang-stats" => app_args.analysis.lang_stats = true,
+                "--dir-stats" => app_args.analysis.dir_stats = true,
+                "--size-histogram" => app_args.analysis.size_histogram = true,
+                "--hotspots" => app_args.analysis.hotspots = true,
+                "--no-merges" => app_args.analysis.no_merges = true,
+                "--no-empty" => app_args.analysis.no_empty = true,
+                "--ignore-whitespace" => app_args.analysis.ignore_whitespace = true,
+                "--ignore-blank-lines" => app_args.analysis.ignore_blank_lines = true,
+                "--no-ignore" => {
+                    app_args.analysis.ignore_whitespace = false;
+                    app_args.analysis.ignore_blank_lines = false;
+                }
+                "--first-parent" => app_args.analysis.first_parent = true,
+                "--normalize-authors" => app_args.analysis.normalize_authors = true,
+                "--lowercase-authors" => {
+                    app_args.analysis.normalize_authors = true;
+                    app_args.analysis.lowercase_authors = true;
+                }
+                "--growth" => app_args.analysis.growth_stats = true,
+                "--author-timeline" => app_args.analysis.author_timeline = true,
+                "--contributor-classes" => {
+                    let min_commits = match args_iter.next_if(|value| value.parse::<usize>().is_ok()) {
+                        Some(min_commits) => parse_positive_number(min_commits, arg)?,
+                        None => DEFAULT_CORE_COMMITS,
+                    };
+                    app_args.analysis.contributor_classes = Some(min_commits);
+                }
+                "--tags" => app_args.analysis.tag_stats = true,
+                "--repo-size" => app_args.analysis.repo_size = true,
+                "--largest-files" => {
+                    // The amount is optional, so that the next argument may also be a path
+                    let amount = match args_iter.next_if(|value| value.parse::<usize>().is_ok()) {
+                        Some(amount) => parse_positive_number(amount, arg)?,
+                        None => DEFAULT_LARGEST_FILES,
+                    };
+                    app_args.analysis.largest_files = Some(amount);
+                }
+                "--coupling" => {
+                    let amount = match args_iter.next_if(|value| value.parse::<usize>().is_ok()) {
+                        Some(amount) => parse_positive_number(amount, arg)?,
+                        None => DEFAULT_COUPLED_FILES,
+                    };
+                    app_args.analysis.coupling = Some(amount);
+                }
+                "--branches" => app_args.analysis.branch_stats = true,
+                "--date-format" => {
+                    app_args.date_format = next_value(&mut args_iter, arg)?;
+                }
+                "--format" => {
+                    let value = next_value(&mut args_iter, arg)?;
+                    app_args.exports.clear();
+                    app_args.export_csv = false;
+                    for format in value.split(',') {
+                        match format {
+                            "text" => app_args.exports.push(OutputFormat::Text),
+                            "markdown" => app_args.exports.push(OutputFormat::Markdown),
+                            "json" => app_args.exports.push(OutputFormat::Json),
+                            "html" => app_args.exports.push(OutputFormat::Html),
+                            "csv" => app_args.export_csv = true,
+                            format => {
+                                return Err(RepolyzerError::BadArgs(format!(
+                                    "Invalid value for {}: '{}' (expected 'text', 'markdown', 'json', 'html' or 'csv')",
+                                    arg, format
+                                )))
+                            }
+                        }
+                    }
+                    if let Some(format) = app_args.exports.first() {
+                        app_args.format = *format;
+                    }
+                }
+                "--json" => {
+                    app_args.format = OutputFormat::Json;
+                    app_args.exports = vec![OutputFormat::Json];
+                    app_args.export_csv = false;
+                }
+                "--theme" => {
+                    app_args.theme = match next_value(&mut args_iter, arg)?.as_str() {
+                        "unicode" => Theme::Unicode,
+                        "ascii" => Theme::Ascii,
+                        "blocks" => Theme::Blocks,
+                        value => {
+                            return Err(RepolyzerError::BadArgs(format!(
+                                "Invalid value for {}: '{}' (expected 'unicode', 'ascii' or 'blocks')",
+                                arg, value
+                            )))
+                        }
+                    }
+                }
+                "--ascii" | "--no-color" => app_args.ascii = true,
+                "--quiet" => app_args.quiet = true,
+                "--deterministic" => app_args.deterministic = true,
+                "--live" => app_args.analysis.live_stats = true,
+                "--force" => app_args.force = true,
+                "--no-cache" => app_args.no_cache = true,
+                "--recurse-submodules" => app_args.recurse_submodules = true,
+                "--watch" => app_args.watch = true,
+                "--watch-interval" => {
+                    let interval = next_value(&mut args_iter, arg)?;
+                    app_args.watch_interval = parse_positive_number(&interval, arg)?;
+                }
+                "--repos-from" => {
+                    let source = next_value(&mut args_iter, arg)?;
+                    let content = if source == "-" {
+                        io::read_to_string(io::stdin())
+                    } else {
+                        std::fs::read_to_string(&source)
+                    }
+                    .map_err(|e| {
+                        RepolyzerError::BadPath(format!(
+                            "Failed to read the repositories from '{}': {}",
+                            source, e
+                        ))
+                    })?;
+                    repository_paths.extend(parse_repository_list(&content));
+                }
+                "--output" => {
+                    app_args.output = Some(PathBuf::from(next_value(&mut args_iter, arg)?))
+                }
+                "--output-dir" => {
+                    app_args.output_dir = Some(PathBuf::from(next_value(&mut args_iter, arg)?))
+                }
+                "--csv" => app_args.csv = Some(PathBuf::from(next_value(&mut args_iter, arg)?)),
+                "--dump-commits" => {
+                    app_args.dump_commits = Some(PathBuf::from(next_value(&mut args_iter, arg)?))
+                }
+                "--ref" => app_args.analysis.reference = Some(next_value(&mut args_iter, arg)?),
+                "--compare" => app_args.analysis.compare = Some(next_value(&mut args_iter, arg)?),
+                "--range" => app_args.analysis.range = Some(next_value(&mut args_iter, arg)?),
+                "--author" => app_args.analysis.author = Some(next_value(&mut args_iter, arg)?),
+                "--exclude-author" => app_args
+                    .analysis
+                    .exclude_authors
+                    .push(next_value(&mut args_iter, arg)?),
+                "--path" => app_args
+                    .analysis
+                    .paths
+                    .push(next_value(&mut args_iter, arg)?),
+                "--ignore-path" => app_args
+                    .analysis
+                    .ignore_paths
+                    .push(next_value(&mut args_iter, arg)?),
+                "--since" => {
+                    let date = next_value(&mut args_iter, arg)?;
+                    app_args.analysis.since = Some(parse_date_bound(&date, arg, false)?);
+                }
+                "--until" => {
+                    let date = next_value(&mut args_iter, arg)?;
+                    app_args.analysis.until = Some(parse_date_bound(&date, arg, true)?);
+                }
+                "--coauthors" => {
+                    app_args.analysis.coauthors = match next_value(&mut args_iter, arg)?.as_str() {
+                        "full" => CoauthorMode::Full,
+                        "split" => CoauthorMode::Split,
+                        value => {
+                            return Err(RepolyzerError::BadArgs(format!(
+                                "Invalid value for {}: '{}' (expected 'full' or 'split')",
+                                arg, value
+                            )))
+                        }
+                    }
+                }
+                "--sample" => {
+                    let rate = next_value(&mut args_iter, arg)?;
+                    app_args.analysis.sample_rate = match rate.parse::<f64>() {
+                        Ok(rate) if rate > 0.0 && rate <= 1.0 => Some(rate),
+                        _ => {
+                            return Err(RepolyzerError::BadArgs(format!(
+                                "Invalid value for {}: '{}' (expected a rate greater than 0 and at most 1)",
+                                arg, rate
+                            )))
+                        }
+                    };
+                }
+                "--churn-merges" => {
+                    app_args.analysis.churn_merges =
+                        match next_value(&mut args_iter, arg)?.as_str() {
+                            "skip" => ChurnMerges::Skip,
+                            "first-parent" => ChurnMerges::FirstParent,
+                            value => {
+                                return Err(RepolyzerError::BadArgs(format!(
+                                    "Invalid value for {}: '{}' (expected 'skip' or 'first-parent')",
+                                    arg, value
+                                )))
+                            }
+                        }
+                }
+                "--diff-algorithm" => {
+                    app_args.analysis.diff_algorithm =
+                        match next_value(&mut args_iter, arg)?.as_str() {
+                            "myers" => DiffAlgorithm::Myers,
+                            "patience" => DiffAlgorithm::Patience,
+                            "minimal" => DiffAlgorithm::Minimal,
+                            "histogram" => {
+                                return Err(RepolyzerError::BadArgs(format!(
+                                    "Invalid value for {}: 'histogram' is not supported by libgit2, try 'patience' instead",
+                                    arg
+                                )))
+                            }
+                            value => {
+                                return Err(RepolyzerError::BadArgs(format!(
+                                    "Invalid value for {}: '{}' (expected 'myers', 'patience' or 'minimal')",
+                                    arg, value
+                                )))
+                            }
+                        }
+                }
+                "--by" => {
+                    app_args.analysis.identity = match next_value(&mut args_iter, arg)?.as_str() {
+                        "author" => Identity::Author,
+                        "committer" => Identity::Committer,
+                        value => {
+                            return Err(RepolyzerError::BadArgs(format!(
+                                "Invalid value for {}: '{}' (expected 'author' or 'committer')",
+                                arg, value
+                            )))
+                        }
+                    }
+                }
+                "--depth" => {
+                    let depth = next_value(&mut args_iter, arg)?;
+                    app_args.depth = Some(parse_positive_number(&depth, arg)?);
+                }
+                "--retries" => {
+                    let retries = next_value(&mut args_iter, arg)?;
+                    app_args.retries = retries.parse().map_err(|_| {
+                        RepolyzerError::BadArgs(format!(
+                            "Invalid value for {}: '{}' (expected a number)",
+                            arg, retries
+                        ))
+                    })?;
+                }
+                "--timeout" => {
+                    let timeout = next_value(&mut args_iter, arg)?;
+                    app_args.timeout = Some(parse_positive_number(&timeout, arg)?);
+                }
+                "--limit" => {
+                    let limit = next_value(&mut args_iter, arg)?;
+                    app_args.analysis.limit = Some(parse_positive_number(&limit, arg)?);
+                }
+                "--top" => {
+                    let top = next_value(&mut args_iter, arg)?;
+                    app_args.top = parse_positive_number(&top, arg)?;
+                }
+                "--min-commits" => {
+                    let min_commits = next_value(&mut args_iter, arg)?;
+                    app_args.min_commits = parse_positive_number(&min_commits, arg)?;
+                }
+                "--width" => {
+                    let width = next_value(&mut args_iter, arg)?;
+                    app_args.width = Some(parse_positive_number(&width, arg)?);
+                }
+                _ => {
+                    return Err(RepolyzerError::BadArgs(format!(
+                        "Unknown argument: {}",
+                        arg
+                    )))
+                }
+            }
+        } else {
+            // Arguments that are neither flags nor values of options are paths
+            repository_paths.push(arg.clone());
+        }
+    }
+
+    if repository_paths.is_empty() {
+        return Err(RepolyzerError::BadPath(
+            "No path provided (neither as argument nor in --repos-from)!".into(),
+        ));
+    }
+    for repository_path in repository_paths {
+        app_args.locations.push(parse_location(&repository_path)?);
+    }
+
+    if let (Some(since), Some(until)) = (app_args.analysis.since, app_args.analysis.until) {
+        if since > until {
+            return Err(RepolyzerError::BadArgs(
+                "The date passed to --since must not be after the one passed to --until!".into(),
+            ));
+        }
+    }
+
+    let analysis = &app_args.analysis;
+    if [&analysis.reference, &analysis.compare, &analysis.range]
+        .iter()
+        .filter(|option| option.is_some())
+        .count()
+        > 1
+    {
+        return Err(RepolyzerError::BadArgs(
+            "Only one of --ref, --compare and --range can be used!".into(),
+        ));
+    }
+
+    // chrono only reports invalid formats while printing, which would be after the whole analysis
+    if StrftimeItems::new(&app_args.date_format).any(|item| item == Item::Error) {
+        return Err(RepolyzerError::BadArgs(format!(
+            "Invalid date format: '{}'",
+            app_args.date_format
+        )));
+    }
+
+    if app_args.watch
+        && app_args
+            .locations
+            .iter()
+            .any(|location| !matches!(location, GitLocation::Local(_)))
+    {
+        return Err(RepolyzerError::BadArgs(
+            "--watch only works with local repositories!".into(),
+        ));
+    }
+
+    if app_args.normalize_weekdays && !app_args.analysis.weekday_stats {
+        return Err(RepolyzerError::BadArgs(
+            "--normalize-weekdays requires the week day stats to be enabled (-w)!".into(),
+        ));
+    }
+
+    match &app_args.output_dir {
+        Some(output_dir) => {
+            if app_args.output.is_some() || app_args.csv.is_some() {
+                return Err(RepolyzerError::BadArgs(
+                    "--output-dir can't be used together with --output or --csv!".into(),
+                ));
+            }
+            if app_args.exports.is_empty() && !app_args.export_csv {
+                // The format of the config file or --json
+                app_args.exports.push(app_args.format);
+            }
+            if app_args.export_csv {
+                if !app_args.analysis.commit_graph {
+                    return Err(RepolyzerError::BadArgs(
+                        "'--format csv' requires the commit graph to be enabled (-c)!".into(),
+                    ));
+                }
+                app_args.csv = Some(output_dir.join(DAILY_CSV_FILE_NAME));
+            }
+            // Only the notes are printed to the console, the stats go into the files
+            app_args.format = OutputFormat::Text;
+        }
+        None if app_args.exports.len() > 1 || app_args.export_csv => {
+            return Err(RepolyzerError::BadArgs(
+                "Multiple formats and 'csv' can only be written with --output-dir!".into(),
+            ));
+        }
+        None => {}
+    }
+
+    if app_args.csv.is_some() && !app_args.analysis.commit_graph {
+        return Err(RepolyzerError::BadArgs(
+            "--csv requires the commit graph to be enabled (-c)!".into(),
+        ));
+    }
+
+    let exports: Vec<PathBuf> = export_paths(&app_args)
+        .into_iter()
+        .map(|(_, path)| path)
+        .collect();
+    for output in app_args
+        .output
+        .iter()
+        .chain(&exports)
+        .chain(app_args.csv.iter())
+        .chain(app_args.dump_commits.iter())
+    {
+        // Check before the analysis, so that a long run isn't wasted
+        if output.exists() && !app_args.force {
+            return Err(RepolyzerError::Output(format!(
+                "The output file '{}' already exists! Use --force to overwrite it.",
+                output.display()
+            )));
+        }
+    }
+
+    // The progress counter would mix with the JSON output
+    app_args.analysis.progress =
+        !app_args.quiet && !app_args.deterministic && app_args.format != OutputFormat::Json;
+    if app_args.deterministic {
+        app_args.analysis.fixed_now = Some(match std::env::var("REPOLYZER_NOW") {
+            Ok(now) => now.parse().map_err(|_| {
+                RepolyzerError::BadArgs(format!(
+                    "Invalid REPOLYZER_NOW '{}', expected the seconds since the epoch",
+                    now
+                ))
+            })?,
+            Err(_) => DETERMINISTIC_NOW,
+        });
+    }
+
+    Ok(app_args)
+}
+
+/// Detects whether the path is a remote URL or a local directory
+fn parse_location(repository_path: &str) -> Result<GitLocation, RepolyzerError> {
+    if repository_path.starts_with("http") {
+        // Remote HTTP(s) URL
+        let url: Url = Url::parse(repository_path)
+            .map_err(|_| RepolyzerError::BadPath("Could not detect valid URL".into()))?;
+        Ok(GitLocation::Remote(url))
+    } else if repository_path.starts_with("git@") || repository_path.starts_with("ssh://") {
+        // Remote SSH URL (either scp-like 'git@host:org/repo.git' or 'ssh://git@host/org/repo.git')
+        Ok(GitLocation::RemoteSsh(repository_path.to_string()))
+    } else {
+        // Assume a local path then
+        let local_path: PathBuf = PathBuf::from(repository_path);
+        if !local_path.exists() || !local_path.is_dir() {
+            return Err(RepolyzerError::BadPath(format!(
+                "The provided path '{}' either does not exist, or is not a directory!",
+                repository_path
+            )));
+        }
+        Ok(GitLocation::Local(local_path))
+    }
+}
+
+/// Gets the paths or URLs of a --repos-from list, one per line, skipping blank lines and '#' comments
+fn parse_repository_list(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Retrieves the value that follows an option, exiting if it is missing
+fn next_value<'a>(
+    args: &mut impl Iterator<Item = &'a String>,
+    option: &str,
+) -> Result<String, RepolyzerError> {
+    args.next()
+        .map(|value| value.to_string())
+        .ok_or_else(|| RepolyzerError::BadArgs(format!("Missing value for argument: {}", option)))
+}
+
+/// Parses a number that has to be greater than zero
+fn parse_positive_number<T: std::str::FromStr + PartialOrd + Default>(
+    value: &str,
+    option: &str,
+) -> Result<T, RepolyzerError> {
+    match value.parse::<T>() {
+        Ok(number) if number > T::default() => Ok(number),
+        _ => Err(RepolyzerError::BadArgs(format!(
+            "Invalid value for {}: '{}' (expected a number greater than zero)",
+            option, value
+        ))),
+    }
+}
+
+/// Parses a date in the format YYYY-MM-DD into a Unix timestamp (in local time).
+/// Depending on `end_of_day`, the first or last second of the day is returned, so that the bounds are inclusive.
+fn parse_date_bound(value: &str, option: &str, end_of_day: bool) -> Result<i64, RepolyzerError> {
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| {
+        RepolyzerError::BadArgs(format!(
+            "Invalid date for {}: '{}' (expected YYYY-MM-DD)",
+            option, value
+        ))
+    })?;
+    let time = if end_of_day {
+        date.and_hms_opt(23, 59, 59).unwrap()
+    } else {
+        date.and_hms_opt(0, 0, 0).unwrap()
+    };
+    let timestamp = match time.and_local_timezone(Local) {
+        chrono::LocalResult::Single(time) => time.timestamp(),
+        chrono::LocalResult::Ambiguous(earliest, latest) => {
+            if end_of_day {
+                latest.timestamp()
+            } else {
+                earliest.timestamp()
+            }
+        }
+        // The local time does not exist (e.g. DST gap), so fall back to interpreting it as UTC
+        chrono::LocalResult::None => time.and_utc().timestamp(),
+    };
+    Ok(timestamp)
+}
+
+/// Serializes the stats of a repository, with the ones of its submodules and the total including them
+fn serialize_result(result: &RepositoryResult) -> serde_json::Value {
+    let mut json = serialize_stats(&result.stats);
+    json["schema_version"] = JSON_SCHEMA_VERSION.into();
+    if !result.submodules.is_empty() {
+        let submodules: Vec<serde_json::Value> = result
+            .submodules
+            .iter()
+            .map(|submodule| {
+                let mut json = serialize_stats(&submodule.stats);
+                json["path"] = submodule.path.as_str().into();
+                json
+            })
+            .collect();
+        json["submodules"] = submodules.into();
+        json["total_with_submodules"] = serialize_stats(&aggregate_stats(result.all_stats()));
+    }
+    json
+}
+
+/// Serializes the stats into a single JSON object.
+/// Optional stats that were not computed are omitted instead of being emitted as zero.
+fn serialize_stats(stats: &RepositoryStats) -> serde_json::Value {
+    let mut json = serde_json::Map::new();
+    if let Some(author_filter) = &stats.author_filter {
+        json.insert("author_filter".into(), author_filter.as_str().into());
+    }
+    if !stats.excluded_authors.is_empty() {
+        json.insert(
+            "excluded_authors".into(),
+            stats.excluded_authors.clone().into(),
+        );
+    }
+    if let Some(analyzed_branch) = &stats.analyzed_branch {
+        json.insert("analyzed_branch".into(), analyzed_branch.as_str().into());
+    }
+    if let Some(compared_range) = &stats.compared_range {
+        json.insert("compared_range".into(), compared_range.as_str().into());
+    }
+    if stats.contributor_identity != Identity::Author {
+        json.insert(
+            "contributor_identity".into(),
+            stats.contributor_identity.to_string().into(),
+        );
+    }
+    if !stats.path_filter.is_empty() {
+        json.insert("path_filter".into(), stats.path_filter.clone().into());
+    }
+    if let Some(commit_limit) = stats.commit_limit {
+        json.insert("commit_limit".into(), commit_limit.into());
+    }
+    json.insert("commit_count".into(), stats.commit_count.into());
+    if stats.first_parent_only {
+        json.insert("first_parent_only".into(), true.into());
+    }
+    if !stats.merges_excluded {
+        json.insert("merge_commits".into(), stats.merge_commits.into());
+    }
+    json.insert(
+        "commits_last_quarter".into(),
+        stats.commits_last_quarter.into(),
+    );
+    json.insert(
+        "commits_prior_quarter".into(),
+        stats.commits_prior_quarter.into(),
+    );
+    if stats.commit_count > 0 {
+        json.insert(
+            "first_commit".into(),
+            format_iso_timestamp(stats.first_commit).into(),
+        );
+        json.insert(
+            "last_commit".into(),
+            format_iso_timestamp(stats.last_commit).into(),
+        );
+    }
+    let contributors: serde_json::Map<String, serde_json::Value> = stats
+        .contributors
+        .iter()
+        .map(|(name, commits)| (name.clone(), commits_to_json(*commits)))
+        .collect();
+    json.insert("contributors".into(), contributors.into());
+
+    if stats.has_extended_stats {
+        json.insert(
+            "diff_algorithm".into(),
+            stats.diff_algorithm.to_string().into(),
+        );
+        json.insert(
+            "total_files_changes".into(),
+            stats.total_files_changes.into(),
+        );
+        json.insert(
+            "binary_files_changed".into(),
+            stats.binary_files_changed.into(),
+        );
+        if !stats.empty_excluded {
+            json.insert("empty_commits".into(), stats.empty_commits.into());
+        }
+        json.insert(
+            "total_lines_inserted".into(),
+            stats.total_lines_inserted.into(),
+        );
+        json.insert(
+            "total_lines_removed".into(),
+            stats.total_lines_removed.into(),
+        );
+        if !stats.is_combined {
+            json.insert(
+                "average_commit_interval".into(),
+                stats.average_commit_interval.into(),
+            );
+            json.insert(
+                "median_commit_interval".into(),
+                stats.median_commit_interval.into(),
+            );
+        }
+        let lines_per_author: serde_json::Map<String, serde_json::Value> = stats
+            .lines_per_author
+            .iter()
+            .map(|(name, (inserted, removed))| {
+                (
+                    name.clone(),
+                    serde_json::json!({ "inserted": inserted, "removed": removed }),
+                )
+            })
+            .collect();
+        json.insert("lines_per_author".into(), lines_per_author.into());
+        json.insert("bus_factor".into(), bus_factor(stats).into());
+        json.insert("gini_coefficient".into(), gini_coefficient(stats).into());
+    }
+
+    if stats.has_commit_graph {
+        json.insert("commits_last_year".into(), stats.commits_last_year.into());
+        json.insert(
+            "longest_commit_streak".into(),
+            stats.longest_commit_streak.into(),
+        );
+        json.insert(
+            "current_commit_streak".into(),
+            stats.current_commit_streak.into(),
+        );
+        json.insert("max_commits_a_day".into(), stats.max_commits_a_day.into());
+        if let Some((date, commits)) = stats.busiest_day() {
+            json.insert(
+                "busiest_day".into(),
+                serde_json::json!({ "date": date.format("%Y-%m-%d").to_string(), "commits": commits }),
+            );
+        }
+        json.insert(
+            "commits_per_day_last_year".into(),
+            stats.commits_per_day_last_year.to_vec().into(),
+        );
+    }
+
+    if stats.has_all_time_heatmap {
+        let commits_per_day_by_year: serde_json::Map<String, serde_json::Value> = stats
+            .commits_per_day_by_year
+            .iter()
+            .map(|(year, days)| {
+                let days_in_year = NaiveDate::from_ymd_opt(*year, 12, 31).unwrap().ordinal();
+                (year.to_string(), days[..days_in_year as usize].into())
+            })
+            .collect();
+        json.insert(
+            "commits_per_day_by_year".into(),
+            commits_per_day_by_year.into(),
+        );
+    }
+
+    if stats.has_weekday_stats {
+        let weekdays = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+        let commits_per_weekday: serde_json::Map<String, serde_json::Value> = weekdays
+            .iter()
+            .zip(stats.commits_per_weekday.iter())
+            .map(|(day, commits)| (day.to_string(), (*commits).into()))
+            .collect();
+        json.insert("commits_per_weekday".into(), commits_per_weekday.into());
+    }
+
+    if stats.has_hour_stats {
+        json.insert(
+            "commits_per_hour".into(),
+            stats.commits_per_hour.to_vec().into(),
+        );
+    }
+
+    if stats.has_coupling {
+        json.insert(
+            "coupled_files".into(),
+            serde_json::to_value(&stats.coupled_files).expect("Failed to serialize coupling"),
+        );
+    }
+
+    if stats.has_tag_stats {
+        json.insert("tag_count".into(), stats.tag_count.into());
+        if let Some((name, time)) = &stats.latest_tag {
+            json.insert(
+                "latest_tag".into(),
+                serde_json::json!({ "name": name, "date": format_iso_timestamp(*time) }),
+            );
+        }
+    }
+
+    if stats.has_branch_stats {
+        let branches: Vec<serde_json::Value> = stats
+            .branches
+            .iter()
+            .map(|branch| {
+                serde_json::json!({
+                    "name": branch.name,
+                    "ahead": branch.ahead,
+                    "behind": branch.behind,
+                    "last_commit": format_iso_timestamp(branch.last_commit),
+                })
+            })
+            .collect();
+        json.insert("branches".into(), branches.into());
+    }
+
+    if stats.has_largest_files {
+        let largest_files: Vec<serde_json::Value> = stats
+            .largest_files
+            .iter()
+            .map(|file| serde_json::json!({ "path": file.path, "size": file.size }))
+            .collect();
+        json.insert("largest_files".into(), largest_files.into());
+    }
+
+    if stats.has_repository_size {
+        json.insert(
+            "repository_size".into(),
+            serde_json::to_value(&stats.repository_size).expect("Failed to serialize size"),
+        );
+    }
+
+    if stats.has_growth_stats {
+        let growth: Vec<serde_json::Value> = stats
+            .contributor_growth
+            .iter()
+            .map(|month| {
+                serde_json::json!({
+                    "month": format!("{}-{:02}", month.year, month.month),
+                    "active_contributors": month.active_contributors,
+                    "total_contributors": month.total_contributors,
+                })
+            })
+            .collect();
+        json.insert("contributor_growth".into(), growth.into());
+    }
+
+    if stats.has_author_timeline {
+        let timeline: serde_json::Map<String, serde_json::Value> = stats
+            .author_first_last
+            .iter()
+            .map(|(author, (first, last))| {
+                (
+                    author.clone(),
+                    serde_json::json!({
+                        "first_commit": format_iso_timestamp(*first),
+                        "last_commit": format_iso_timestamp(*last),
+                        "active": is_active(stats, *last),
+                    }),
+                )
+            })
+            .collect();
+        json.insert("author_timeline".into(), timeline.into());
+    }
+
+    if stats.has_contributor_classes {
+        let classes = contributor_classes(stats);
+        json.insert(
+            "contributor_classes".into(),
+            serde_json::json!({
+                "min_core_commits": stats.min_core_commits,
+                "core": {
+                    "contributors": classes.core,
+                    "commits": classes.core_commits,
+                },
+                "occasional": {
+                    "contributors": classes.occasional,
+                    "commits": classes.occasional_commits,
+                },
+            }),
+        );
+    }
+
+    if stats.has_lang_stats {
+        let lines_per_extension: serde_json::Map<String, serde_json::Value> = stats
+            .lines_per_extension
+            .iter()
+            .map(|(extension, (inserted, removed))| {
+                (
+                    extension.clone(),
+                    serde_json::json!({ "inserted": inserted, "removed": removed }),
+                )
+            })
+            .collect();
+        json.insert("lines_per_extension".into(), lines_per_extension.into());
+    }
+
+    if stats.has_dir_stats {
+        let lines_per_directory: serde_json::Map<String, serde_json::Value> = stats
+            .lines_per_directory
+            .iter()
+            .map(|(directory, (inserted, removed))| {
+                (
+                    directory.clone(),
+                    serde_json::json!({ "inserted": inserted, "removed": removed }),
+                )
+            })
+            .collect();
+        json.insert("lines_per_directory".into(), lines_per_directory.into());
+    }
+
+    if let Some((sampled, diffable)) = stats.churn_sample {
+        json.insert(
+            "churn_sample".into(),
+            serde_json::json!({ "sampled_commits": sampled, "diffable_commits": diffable }),
+        );
+    }
+
+    if stats.has_message_stats {
+        json.insert(
+            "average_subject_length".into(),
+            average_subject_length(stats).into(),
+        );
+        json.insert("long_messages".into(), stats.long_messages.into());
+        json.insert(
+            "conventional_commits".into(),
+            stats.conventional_commits.into(),
+        );
+        json.insert("revert_commits".into(), stats.revert_commits.into());
+        json.insert("fixup_commits".into(), stats.fixup_commits.into());
+    }
+
+    if stats.has_hotspots {
+        json.insert(
+            "commits_per_file".into(),
+            serde_json::to_value(&stats.commits_per_file).expect("Failed to serialize commits"),
+        );
+    }
+
+    if stats.has_size_histogram {
+        json.insert(
+            "commit_size_buckets".into(),
+            stats.commit_size_buckets.to_vec().into(),
+        );
+    }
+
+    if stats.has_signature_stats {
+        json.insert("signed_commits".into(), stats.signed_commits.into());
+    }
+
+    serde_json::Value::Object(json)
+}
+
+/// Formats the busiest day of the commit graph with its commits, e.g. "2024-03-12 (17 commits)"
+fn format_busiest_day(stats: &RepositoryStats) -> String {
+    match stats.busiest_day() {
+        Some((date, 1)) => format!("{} (1 commit)", date.format("%Y-%m-%d")),
+        Some((date, commits)) => format!("{} ({} commits)", date.format("%Y-%m-%d"), commits),
+        None => "none".to_string(),
+    }
+}
+
+/// Converts an amount of commits into JSON, keeping whole amounts as integers
+fn commits_to_json(commits: f64) -> serde_json::Value {
+    if commits.fract() == 0.0 {
+        (commits as u64).into()
+    } else {
+        commits.into()
+    }
+}
+
+/// Formats an amount of commits, which is only fractional if commits are split between co-authors
+fn format_commits(commits: f64) -> String {
+    if commits.fract() == 0.0 {
+        format!("{:.0}", commits)
+    } else {
+        format!("{:.2}", commits)
+    }
+}
+
+/// Formats the empty commit amount together with its share of all commits
+fn format_empty_commits(stats: &RepositoryStats) -> String {
+    if stats.empty_excluded {
+        return "excluded".to_string();
+    }
+    format_commit_share(stats, stats.empty_commits)
+}
+
+/// Formats the merge commit amount together with the share of non-merge commits
+fn format_merge_commits(stats: &RepositoryStats) -> String {
+    if stats.merges_excluded {
+        return "excluded".to_string();
+    }
+    if stats.commit_count == 0 {
+        return stats.merge_commits.to_string();
+    }
+    let non_merge_percentage =
+        (stats.commit_count - stats.merge_commits) as f64 / stats.commit_count as f64 * 100.0;
+    format!(
+        "{} ({:.1}% non-merge)",
+        stats.merge_commits, non_merge_percentage
+    )
+}
+
+/// Collects the rows of the overview, each consisting of the dotted text label and the value.
+/// The extended overview additionally contains the stats about the changed lines.
+fn overview_rows(
+    stats: &RepositoryStats,
+    extended: bool,
+    date_format: &str,
+) -> Vec<(&'static str, String)> {
+    let mut rows = Vec::new();
+    if let Some(compared_range) = &stats.compared_range {
+        rows.push(("Compared range ........", compared_range.clone()));
+    } else if let Some(analyzed_branch) = &stats.analyzed_branch {
+        rows.push(("Analyzed branch .......", analyzed_branch.clone()));
+    }
+    rows.extend([
+        ("Commit amount .........", stats.commit_count.to_string()),
+        ("Merge commits .........", format_merge_commits(stats)),
+        (
+            "First commit ..........",
+            format_commit_date(stats, stats.first_commit, date_format),
+        ),
+        (
+            "Last commit ...........",
+            format_commit_date(stats, stats.last_commit, date_format),
+        ),
+        ("Repository age ........", format_repository_age(stats)),
+        (
+            "Contributor amount ....",
+            stats.contributors.len().to_string(),
+        ),
+        ("Top contributor .......", format_top_contributor(stats)),
+        ("Trend .................", format_trend(stats)),
+    ]);
+    if stats.contributor_identity != Identity::Author {
+        rows.push((
+            "Contributors by .......",
+            stats.contributor_identity.to_string(),
+        ));
+    }
+    if let Some(author_filter) = &stats.author_filter {
+        rows.push(("Author filter .........", author_filter.clone()));
+    }
+    if !stats.excluded_authors.is_empty() {
+        rows.push(("Excluded authors ......", stats.excluded_authors.join(", ")));
+    }
+    if !stats.path_filter.is_empty() {
+        rows.push(("Path filter ...........", stats.path_filter.join(", ")));
+    }
+    if stats.first_parent_only {
+        rows.push(("History ...............", "first parent only".to_string()));
+    }
+    if let Some(commit_limit) = stats.commit_limit {
+        rows.push((
+            "Commit limit ..........",
+            format!("only the {} most recent commits", commit_limit),
+        ));
+    }
+    if extended {
+        let delta = stats.total_lines_inserted as i64 - stats.total_lines_removed as i64;
+        if let Some(churn_sample) = stats.churn_sample {
+            rows.push(("Changed lines sample ..", format_churn_sample(churn_sample)));
+        }
+        rows.push(("Diff algorithm ........", stats.diff_algorithm.to_string()));
+        rows.extend([
+            (
+                "Files changed .........",
+                stats.total_files_changes.to_string(),
+            ),
+            (
+                "Binary files changed ..",
+                stats.binary_files_changed.to_string(),
+            ),
+            ("Empty commits .........", format_empty_commits(stats)),
+            (
+                "Lines inserted.........",
+                stats.total_lines_inserted.to_string(),
+            ),
+            (
+                "Lines removed .........",
+                stats.total_lines_removed.to_string(),
+            ),
+            ("Total lines (delta) ...", delta.to_string()),
+            ("Add./Del. ratio........", format_add_del_ratio(stats)),
+        ]);
+        // The intervals between the commits of different repositories are unknown
+        if !stats.is_combined {
+            rows.extend([
+                (
+                    "Average interval ......",
+                    format_interval(stats.average_commit_interval),
+                ),
+                (
+                    "Median interval .......",
+                    format_interval(stats.median_commit_interval),
+                ),
+            ]);
+        }
+        rows.extend([
+            ("Bus factor ............", bus_factor(stats).to_string()),
+            (
+                "Gini coefficient ......",
+                format!("{:.2}", gini_coefficient(stats)),
+            ),
+        ]);
+    }
+    rows
+}
+
+fn print_overview(
+    out: &mut dyn Write,
+    stats: &RepositoryStats,
+    extended: bool,
+    date_format: &str,
+) -> io::Result<()> {
+    writeln!(out, "-------------------------------------")?;
+    writeln!(out, "Overall commit stats:")?;
+    for (label, value) in overview_rows(stats, extended, date_format) {
+        writeln!(out, "{} {}", label, value)?;
+    }
+    writeln!(out, "-------------------------------------")?;
+    Ok(())
+}
+
+fn print_markdown_overview(
+    out: &mut dyn Write,
+    stats: &RepositoryStats,
+    extended: bool,
+    date_format: &str,
+) -> io::Result<()> {
+    writeln!(out, "### Overall commit stats")?;
+    writeln!(out)?;
+    writeln!(out, "| Stat | Value |")?;
+    writeln!(out, "| --- | --- |")?;
+    for (label, value) in overview_rows(stats, extended, date_format) {
+        let label = label.trim_end_matches(['.', ' ']);
+        writeln!(
+            out,
+            "| {} | {} |",
+            escape_markdown(label),
+            escape_markdown(&value)
+        )?;
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+/// Escapes characters that would break a Markdown table
+fn escape_markdown(value: &str) -> String {
+    value.replace('|', "\\|")
+}
+
+/// Prints the output of a text printer as a fenced code block, using its title as heading
+fn print_markdown_code_block(
+    out: &mut dyn Write,
+    printer: impl FnOnce(&mut dyn Write) -> io::Result<()>,
+) -> io::Result<()> {
+    let mut buffer: Vec<u8> = Vec::new();
+    printer(&mut buffer)?;
+    let text = String::from_utf8_lossy(&buffer);
+
+    // The separator lines are only needed in the text output
+    let mut lines = text
+        .lines()
+        .filter(|line| line.is_empty() || !line.chars().all(|c| c == '-'))
+        .peekable();
+    if let Some(title) = lines.next_if(|line| line.ends_with(':')) {
+        writeln!(out, "### {}", title.trim_end_matches(':'))?;
+        writeln!(out)?;
+    }
+    writeln!(out, "```text")?;
+    for line in lines {
+        writeln!(out, "{}", line)?;
+    }
+    writeln!(out, "```")?;
+    writeln!(out)?;
+    Ok(())
+}
+
+/// Calculates the minimum number of top contributors that made more than half of all commits
+fn bus_factor(stats: &RepositoryStats) -> usize {
+    let total: f64 = stats.contributors.values().sum();
+    let mut commits_so_far = 0.0;
+    for (i, (_, commits)) in top_contributors(stats, stats.contributors.len(), 1)
+        .iter()
+        .enumerate()
+    {
+        commits_so_far += commits;
+        if commits_so_far * 2.0 > total {
+            return i + 1;
+        }
+    }
+    0
+}
+
+/// Calculates the Gini coefficient of the commits per contributor.
+/// 0 means that everyone made the same amount of commits, values close to 1 that a few made nearly all.
+fn gini_coefficient(stats: &RepositoryStats) -> f64 {
+    let total: f64 = stats.contributors.values().sum();
+    if total == 0.0 {
+        return 0.0;
+    }
+
+    // Ascending by commits, as required by the formula
+    let mut commits: Vec<f64> = stats.contributors.values().copied().collect();
+    commits.sort_by(f64::total_cmp);
+    let n = commits.len() as f64;
+    let weighted_sum: f64 = commits
+        .iter()
+        .enumerate()
+        .map(|(i, commits)| (i + 1) as f64 * commits)
+        .sum();
+    2.0 * weighted_sum / (n * total) - (n + 1.0) / n
+}
+
+/// Formats the date of a commit, or states that there are none
+fn format_commit_date(stats: &RepositoryStats, timestamp: u64, date_format: &str) -> String {
+    if stats.commit_count == 0 {
+        return "no commits".to_string();
+    }
+    format_timestamp(timestamp, date_format)
+}
+
+/// Formats a Unix timestamp for the text output, with the format of --date-format
+fn format_timestamp(timestamp: u64, date_format: &str) -> String {
+    let dt = DT::from_timestamp(timestamp as i64, 0).unwrap();
+    dt.format(date_format).to_string()
+}
+
+/// Formats a Unix timestamp as ISO 8601 in UTC for the JSON output
+fn format_iso_timestamp(timestamp: u64) -> String {
+    let dt = DT::from_timestamp(timestamp as i64, 0).unwrap();
+    dt.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// Formats the time since the first commit in years, months and days
+fn format_repository_age(stats: &RepositoryStats) -> String {
+    if stats.commit_count == 0 {
+        return "no commits".to_string();
+    }
+    let first_commit = DT::from_timestamp(stats.first_commit as i64, 0)
+        .unwrap()
+        .with_timezone(&Local)
+        .date_naive();
+    let today = stats.today();
+
+    // Count the full months first, the remainder are days
+    let mut months = (today.year() - first_commit.year()) * 12 + today.month() as i32
+        - first_commit.month() as i32;
+    if today.day() < first_commit.day() {
+        months -= 1;
+    }
+    let months = months.max(0) as u32;
+    let days = (today - (first_commit + Months::new(months))).num_days();
+
+    let plural = |amount: i64, unit: &str| {
+        format!("{} {}{}", amount, unit, if amount == 1 { "" } else { "s" })
+    };
+    format!(
+        "{}, {}, {}",
+        plural((months / 12) as i64, "year"),
+        plural((months % 12) as i64, "month"),
+        plural(days, "day")
+    )
+}
+
+/// Formats the time between commits with its two largest units, like "2d 4h"
+fn format_interval(seconds: Option<u64>) -> String {
+    let Some(seconds) = seconds else {
+        return "n/a".to_string();
+    };
+    let (days, hours) = (seconds / 86_400, seconds % 86_400 / 3_600);
+    let (minutes, seconds) = (seconds % 3_600 / 60, seconds % 60);
+    if days > 0 {
+        format!("{}d {}h", days, hours)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else if minutes > 0 {
+        format!("{}m {}s", minutes, seconds)
+    } else {
+        format!("{}s", seconds)
+    }
+}
+
+/// Formats the ratio of added to deleted lines, taking care of repositories without deletions
+fn format_add_del_ratio(stats: &RepositoryStats) -> String {
+    match (stats.total_lines_inserted, stats.total_lines_removed) {
+        (0, 0) => "n/a".to_string(),
+        (_, 0) => "∞ (no deletions)".to_string(),
+        (inserted, removed) => format!("{:.2}", inserted as f64 / removed as f64),
+    }
+}
+
+/// Collects the (up to) ten authors with the most net lines changed as (name, inserted, removed, net)
+fn top_authors_by_net_lines(stats: &RepositoryStats) -> Vec<(&String, usize, usize, i64)> {
+    const LISTED_AUTHORS: usize = 10;
+
+    // Sort descending by net lines changed
+    let mut authors: Vec<(&String, usize, usize, i64)> = stats
+        .lines_per_author
+        .iter()
+        .map(|(name, (inserted, removed))| {
+            (
+                name,
+                *inserted,
+                *removed,
+                *inserted as i64 - *removed as i64,
+            )
+        })
+        .collect();
+    authors.sort_by(|a, b| b.3.cmp(&a.3).then(a.0.cmp(b.0)));
+    authors.truncate(LISTED_AUTHORS);
+    authors
+}
+
+fn print_lines_per_author(out: &mut dyn Write, stats: &RepositoryStats) -> io::Result<()> {
+    writeln!(
+        out,
+        "Top {}s by net lines changed:",
+        stats.contributor_identity
+    )?;
+    for (name, inserted, removed, net) in top_authors_by_net_lines(stats) {
+        writeln!(
+            out,
+            "\t{}\t+{}\t-{}\t(net {})",
+            name, inserted, removed, net
+        )?;
+    }
+    writeln!(out, "-------------------------------------")?;
+    Ok(())
+}
+
+fn print_markdown_lines_per_author(out: &mut dyn Write, stats: &RepositoryStats) -> io::Result<()> {
+    let (title, column) = match stats.contributor_identity {
+        Identity::Author => ("authors", "Author"),
+        Identity::Committer => ("committers", "Committer"),
+    };
+    writeln!(out, "### Top {} by net lines changed", title)?;
+    writeln!(out)?;
+    writeln!(out, "| {} | Inserted | Removed | Net |", column)?;
+    writeln!(out, "| --- | ---: | ---: | ---: |")?;
+    for (name, inserted, removed, net) in top_authors_by_net_lines(stats) {
+        writeln!(
+            out,
+            "| {} | +{} | -{} | {} |",
+            escape_markdown(name),
+            inserted,
+            removed,
+            net
+        )?;
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+fn print_lang_stats(out: &mut dyn Write, stats: &RepositoryStats) -> io::Result<()> {
+    writeln!(out, "-------------------------------------")?;
+    writeln!(out, "Top file extensions by lines changed:")?;
+    print_top_churn(out, &stats.lines_per_extension)
+}
+
+/// Prints the (up to) ten files that were changed by the most commits
+fn print_hotspots(out: &mut dyn Write, stats: &RepositoryStats) -> io::Result<()> {
+    writeln!(out, "-------------------------------------")?;
+    writeln!(out, "Top files by commits:")?;
+    let mut files: Vec<(&String, &usize)> = stats.commits_per_file.iter().collect();
+    files.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+    for (path, commits) in files.iter().take(LISTED_ENTRIES) {
+        writeln!(out, "\t{}\t{}", commits, path)?;
+    }
+    Ok(())
+}
+
+/// Prints the pairs of files that were changed together by the most commits
+fn print_coupling(out: &mut dyn Write, stats: &RepositoryStats) -> io::Result<()> {
+    writeln!(out, "-------------------------------------")?;
+    writeln!(out, "Files changed together most often:")?;
+    for pair in &stats.coupled_files {
+        writeln!(
+            out,
+            "\t{}\t{} <-> {}",
+            pair.commits, pair.first, pair.second
+        )?;
+    }
+    Ok(())
+}
+
+fn print_dir_stats(out: &mut dyn Write, stats: &RepositoryStats) -> io::Result<()> {
+    writeln!(out, "-------------------------------------")?;
+    writeln!(out, "Top directories by lines changed:")?;
+    print_top_churn(out, &stats.lines_per_directory)
+}
+
+/// Prints the (up to) ten entries with the most changed lines
+fn print_top_churn(out: &mut dyn Write, lines: &HashMap<String, (usize, usize)>) -> io::Result<()> {
+    // Sort descending by total churn
+    let mut entries: Vec<(&String, &(usize, usize))> = lines.iter().collect();
+    let churn = |(inserted, removed): &(usize, usize)| inserted + removed;
+    entries.sort_by(|a, b| churn(b.1).cmp(&churn(a.1)).then(a.0.cmp(b.0)));
+
+    for (name, (inserted, removed)) in entries.iter().take(LISTED_ENTRIES) {
+        writeln!(
+            out,
+            "\t{}\t+{}\t-{}\t(total {})",
+            name,
+            inserted,
+            removed,
+            inserted + removed
+        )?;
+    }
+    Ok(())
+}
+
+/// Prints the pie chart, naming the `named` contributors with the most (and at least `min_commits`) commits
+fn print_pie_chart(
+    out: &mut dyn Write,
+    stats: &RepositoryStats,
+    named: usize,
+    min_commits: usize,
+    ascii: bool,
+) -> io::Result<()> {
+    writeln!(out, "Commit pie chart:")?;
+
+    let top_contributors = top_contributors(stats, named, min_commits);
+    if top_contributors.is_empty() {
+        writeln!(out, "No commits to chart!")?;
+        return Ok(());
+    }
+    if ascii {
+        print_contributor_bars(out, &top_contributors)?;
+        return Ok(());
+    }
+
+    // Create chart
+    Chart::new()
+        .radius(9)
+        .aspect_ratio(3)
+        .legend(true)
+        .draw_into(&mut *out, &pie_chart_data(&top_contributors))
+}
+
+/// Creates the slices of the pie chart, cycling through the colors and symbols if there are more slices than them
+fn pie_chart_data(top_contributors: &[(String, f64)]) -> Vec<Data> {
+    const SYMBOLS: [char; 6] = ['•', '▪', '▴', '◆', '⬟', '◆'];
+
+    top_contributors
+        .iter()
+        .enumerate()
+        .map(|(i, (name, commits))| {
+            let (r, g, b) = PIE_CHART_COLORS[i % PIE_CHART_COLORS.len()];
+            Data {
+                label: name.to_string(),
+                value: *commits as f32,
+                color: Some(Color::RGB(r, g, b).into()),
+                fill: SYMBOLS[i % SYMBOLS.len()],
+            }
+        })
+        .collect()
+}
+
+/// Collects the (up to `named`) contributors with the most commits, sorted descending by commit amount.
+/// All remaining contributors, including the ones with less than `min_commits` commits, are summed up as "Others".
+fn top_contributors(
+    stats: &RepositoryStats,
+    named: usize,
+    min_commits: usize,
+) -> Vec<(String, f64)> {
+    let mut contributors: Vec<(&String, &f64)> = stats.contributors.iter().collect();
+    contributors.sort_by(|a, b| b.1.total_cmp(a.1).then(a.0.cmp(b.0)));
+    let named = named.min(named_contributors(stats, min_commits));
+
+    let mut top_contributors: Vec<(String, f64)> = contributors
+        .iter()
+        .take(named)
+        .map(|(name, commits)| (name.to_string(), **commits))
+        .collect();
+
+    // Add "Others" if there are more than `named` contributors
+    let others: f64 = contributors
+        .iter()
+        .skip(named)
+        .map(|(_, commits)| **commits)
+        .sum();
+    if others > 0.0 {
+        top_contributors.push(("Others".to_string(), others));
+    }
+    top_contributors
+}
+
+/// Counts the contributors with at least `min_commits` commits.
+/// A share of a commit split between co-authors counts as a whole commit.
+fn named_contributors(stats: &RepositoryStats, min_commits: usize) -> usize {
+    stats
+        .contributors
+        .values()
+        .filter(|commits| commits.ceil() >= min_commits as f64)
+        .count()
+}
+
+/// Collects the `named` contributors with the most commits and the summary of the remaining ones,
+/// as (name, commits, percentage of all commits)
+fn contributor_list(
+    stats: &RepositoryStats,
+    named: usize,
+    min_commits: usize,
+) -> Vec<(String, f64, f64)> {
+    let named = named.min(named_contributors(stats, min_commits));
+    let others = stats.contributors.len() - named;
+    top_contributors(stats, named, min_commits)
+        .into_iter()
+        .enumerate()
+        .map(|(i, (name, commits))| {
+            let percentage = commits / stats.commit_count as f64 * 100.0;
+            // "Others" follows the named contributors and is only present if contributors were left out
+            let name = if i == named {
+                format!("{} other contributors", others)
+            } else {
+                name
+            };
+            (name, commits, percentage)
+        })
+        .collect()
+}
+
+/// Lists the `named` contributors with the most commits, summarizing the remaining ones
+fn print_contributors(
+    out: &mut dyn Write,
+    stats: &RepositoryStats,
+    named: usize,
+    min_commits: usize,
+) -> io::Result<()> {
+    writeln!(out, "Contributors by commits:")?;
+    for (name, commits, percentage) in contributor_list(stats, named, min_commits) {
+        writeln!(
+            out,
+            "\t{}: {} ({:.1}%)",
+            name,
+            format_commits(commits),
+            percentage
+        )?;
+    }
+    writeln!(out, "-------------------------------------")?;
+    Ok(())
+}
+
+fn print_markdown_contributors(
+    out: &mut dyn Write,
+    stats: &RepositoryStats,
+    named: usize,
+    min_commits: usize,
+) -> io::Result<()> {
+    writeln!(out, "### Contributors by commits")?;
+    writeln!(out)?;
+    writeln!(out, "| Contributor | Commits | Share |")?;
+    writeln!(out, "| --- | ---: | ---: |")?;
+    for (name, commits, percentage) in contributor_list(stats, named, min_commits) {
+        writeln!(
+            out,
+            "| {} | {} | {:.1}% |",
+            escape_markdown(&name),
+            format_commits(commits),
+            percentage
+        )?;
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+/// Plain text alternative to the pie chart, which is readable without colors and Unicode
+fn print_contributor_bars(
+    out: &mut dyn Write,
+    top_contributors: &[(String, f64)],
+) -> io::Result<()> {
+    const BAR_WIDTH: f64 = 40.0;
+
+    let total: f64 = top_contributors.iter().map(|(_, commits)| commits).sum();
+    for (name, commits) in top_contributors {
+        let share = commits / total;
+        writeln!(
+            out,
+            "\t{}: {} ({:.1}%)\t|{}",
+            name,
+            format_commits(*commits),
+            share * 100.0,
+            "#".repeat((share * BAR_WIDTH).round() as usize)
+        )?;
+    }
+    Ok(())
+}
+
+fn print_commit_checker_board(
+    out: &mut dyn Write,
+    stats: &RepositoryStats,
+    symbols: &[char; CHECKERBOARD_SYMBOL_AMOUNT],
+    width: usize,
+) -> io::Result<()> {
+    let distribution = symbol_thresholds(stats.max_commits_a_day);
+    print_distribution_legend(out, &distribution, symbols)?;
+
+    let first_day = stats.today() - chrono::Duration::days(DAYS_IN_GRAPH as i64 - 1);
+    writeln!(out, "{}", box_rule('╔', width))?;
+    writeln!(out, "║\tCommits in the last year: {} | Longest Streak: {} days | Current Streak: {} days | Max a day: {}"
+        , stats.commits_last_year, stats.longest_commit_streak, stats.current_commit_streak, stats.max_commits_a_day)?;
+    writeln!(out, "║\tBusiest day: {}", format_busiest_day(stats))?;
+    writeln!(out, "{}", box_rule('╠', width))?;
+    print_checker_board_rows(
+        out,
+        &stats.commits_per_day_last_year,
+        first_day,
+        &distribution,
+        symbols,
+        width,
+    )?;
+    writeln!(out, "{}", box_rule('╚', width))?;
+    Ok(())
+}
+
+/// Prints one checker board per year of the whole history, all sharing the same distribution
+fn print_all_time_heatmap(
+    out: &mut dyn Write,
+    stats: &RepositoryStats,
+    symbols: &[char; CHECKERBOARD_SYMBOL_AMOUNT],
+    width: usize,
+) -> io::Result<()> {
+    let max_commits_a_day = stats
+        .commits_per_day_by_year
+        .values()
+        .flat_map(|days| days.iter().copied())
+        .max()
+        .unwrap_or(0);
+    let distribution = symbol_thresholds(max_commits_a_day);
+    print_distribution_legend(out, &distribution, symbols)?;
+
+    let mut years: Vec<(&i32, &[usize; MAX_DAYS_IN_YEAR])> =
+        stats.commits_per_day_by_year.iter().collect();
+    years.sort_by_key(|(year, _)| **year);
+    for (year, days) in years {
+        let first_day = NaiveDate::from_ymd_opt(*year, 1, 1).unwrap();
+        let last_day = NaiveDate::from_ymd_opt(*year, 12, 31).unwrap();
+        let days = &days[..last_day.ordinal() as usize];
+
+        writeln!(out, "{}", box_rule('╔', width))?;
+        writeln!(
+            out,
+            "║\tCommits in {}: {} | Max a day: {}",
+            year,
+            days.iter().sum::<usize>(),
+            days.iter().max().unwrap()
+        )?;
+        writeln!(out, "{}", box_rule('╠', width))?;
+        print_checker_board_rows(out, days, first_day, &distribution, symbols, width)?;
+        writeln!(out, "{}", box_rule('╚', width))?;
+    }
+    Ok(())
+}
+
+/// Prints the month header and a row per weekday for the commits per day, starting at `first_day`.
+/// The oldest weeks are left out if not all of them fit into `width`.
+fn print_checker_board_rows(
+    out: &mut dyn Write,
+    days: &[usize],
+    first_day: NaiveDate,
+    distribution: &[usize; CHECKERBOARD_SYMBOL_AMOUNT],
+    symbols: &[char; CHECKERBOARD_SYMBOL_AMOUNT],
+    width: usize,
+) -> io::Result<()> {
+    let weekdays = [
+        (chrono::Weekday::Mon, "Mon"),
+        (chrono::Weekday::Tue, "Tue"),
+        (chrono::Weekday::Wed, "Wed"),
+        (chrono::Weekday::Thu, "Thu"),
+        (chrono::Weekday::Fri, "Fri"),
+        (chrono::Weekday::Sat, "Sat"),
+        (chrono::Weekday::Sun, "Sun"),
+    ];
+    let rows = weekdays.map(|(weekday, name)| {
+        (
+            name,
+            calculate_day_commit_graph(days, first_day, weekday, distribution, symbols),
+        )
+    });
+
+    // Each week is a column of two characters
+    let weeks = rows
+        .iter()
+        .map(|(_, row)| row.chars().count() / 2)
+        .max()
+        .unwrap_or(0);
+    let hidden_weeks = weeks.saturating_sub(width.saturating_sub(CHECKERBOARD_LABEL_WIDTH) / 2);
+    writeln!(out, "║{}", month_header(first_day, weeks, hidden_weeks))?;
+    for (name, row) in rows {
+        let row: String = row.chars().skip(2 * hidden_weeks).collect();
+        writeln!(out, "║ {}\t{}", name, row)?;
+    }
+    Ok(())
+}
+
+/// Labels the columns of the checker board in which a month starts, skipping the first `hidden_weeks` columns
+fn month_header(first_day: NaiveDate, weeks: usize, hidden_weeks: usize) -> String {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+    let first_monday =
+        first_day - chrono::Duration::days(first_day.weekday().num_days_from_monday() as i64);
+    // A column belongs to the month of its Monday, so that the label is above the first full week
+    let month_of = |week: usize| (first_monday + chrono::Duration::weeks(week as i64)).month0();
+
+    // Without '║', so that the symbols of the rows start after the label width
+    let mut header = " ".repeat(CHECKERBOARD_LABEL_WIDTH - 1);
+    for week in hidden_weeks..weeks {
+        let month = month_of(week);
+        let starts_month = if week == hidden_weeks {
+            // The label of the first column would run into the next one if that starts another month
+            month_of(week + 1) == month
+        } else {
+            month_of(week - 1) != month
+        };
+        let column = CHECKERBOARD_LABEL_WIDTH + 2 * (week - hidden_weeks);
+        if starts_month && header.len() < column {
+            header.push_str(&" ".repeat(column - header.len()));
+            header.push_str(MONTHS[month as usize]);
+        }
+    }
+    header
+}
+
+/// Draws a horizontal rule of the checker board box that spans `width` columns
+fn box_rule(corner: char, width: usize) -> String {
+    format!("{}{}", corner, "═".repeat(width.saturating_sub(1)))
+}
+
+/// Calculates how many columns the bars may take up, so that the longest one still fits into `width`
+fn bar_width(width: usize) -> usize {
+    // Very narrow widths would leave no room to compare the bars
+    width.saturating_sub(BAR_LABEL_WIDTH).max(10)
+}
+
+/// Gets the columns to fit the output into, either as given by --width or from the terminal
+fn output_width(app_args: &AppArgs) -> usize {
+    if let Some(width) = app_args.width {
+        return width;
+    }
+    // Output that is written to a file or piped has no width to adapt to
+    if app_args.deterministic || app_args.output.is_some() || !io::stdout().is_terminal() {
+        return DEFAULT_WIDTH;
+    }
+    std::env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.parse().ok())
+        .filter(|columns| *columns > 0)
+        .or_else(terminal_width)
+        .unwrap_or(DEFAULT_WIDTH)
+}
+
+/// Asks the terminal of stdout for its width
+fn terminal_width() -> Option<usize> {
+    let (terminal_size::Width(columns), _) = terminal_size::terminal_size()?;
+    (columns > 0).then_some(columns as usize)
+}
+
+/// Prints a bar per weekday, either for the total commits or for the commits per occurrence of the weekday
+fn print_weekday_stats(
+    out: &mut dyn Write,
+    stats: &RepositoryStats,
+    normalize: bool,
+    width: usize,
+) -> io::Result<()> {
+    let commits: [f64; 7] = if normalize {
+        let occurrences = weekday_occurrences(stats);
+        std::array::from_fn(|i| stats.commits_per_weekday[i] as f64 / occurrences[i].max(1) as f64)
+    } else {
+        stats.commits_per_weekday.map(|commits| commits as f64)
+    };
+    let bar_width = bar_width(width) as f64;
+    let max_commits = commits.iter().copied().fold(0.0, f64::max);
+
+    writeln!(out, "-------------------------------------")?;
+    if normalize {
+        writeln!(out, "Commits per occurrence of the weekday:")?;
+    } else {
+        writeln!(out, "Commits per weekday:")?;
+    }
+    if max_commits == 0.0 {
+        // The bars would be scaled by zero
+        return writeln!(out, "No commits");
+    }
+    for (i, commits) in commits.iter().enumerate() {
+        let percentage = (commits / max_commits * bar_width) as usize;
+        let weekday = match i {
+            0 => "Mon",
+            1 => "Tue",
+            2 => "Wed",
+            3 => "Thu",
+            4 => "Fri",
+            5 => "Sat",
+            6 => "Sun",
+            _ => "???", // Should/Can never happen
+        };
+        let value = if normalize {
+            format!("{:.2}", commits)
+        } else {
+            stats.commits_per_weekday[i].to_string()
+        };
+        writeln!(out, "\t{}\t{}\t|{}", weekday, value, "█".repeat(percentage))?;
+    }
+    Ok(())
+}
+
+/// Counts how often each weekday (starting at Monday) occurs from the day of the first commit up to the last one
+fn weekday_occurrences(stats: &RepositoryStats) -> [usize; 7] {
+    let mut occurrences = [0; 7];
+    let Some((first, last)) = stats.weekday_window else {
+        return occurrences;
+    };
+    let first_day = NaiveDate::from_num_days_from_ce_opt(first).unwrap();
+    let days = i64::from(last - first) + 1;
+    // Every full week contains each weekday once, the remaining days start at the weekday of the first day
+    for (i, occurrence) in occurrences.iter_mut().enumerate() {
+        let offset = (i as i64 - first_day.weekday().num_days_from_monday() as i64).rem_euclid(7);
+        *occurrence = (days / 7 + i64::from(offset < days % 7)) as usize;
+    }
+    occurrences
+}
+
+fn print_tag_stats(
+    out: &mut dyn Write,
+    stats: &RepositoryStats,
+    date_format: &str,
+) -> io::Result<()> {
+    writeln!(out, "-------------------------------------")?;
+    writeln!(out, "Tag stats:")?;
+    writeln!(out, "Tag amount ............ {}", stats.tag_count)?;
+    let latest_tag = match &stats.latest_tag {
+        Some((name, time)) => format!("{} ({})", name, format_timestamp(*time, date_format)),
+        None => "no tags".to_string(),
+    };
+    writeln!(out, "Latest tag ............ {}", latest_tag)?;
+    Ok(())
+}
+
+fn print_branch_stats(
+    out: &mut dyn Write,
+    stats: &RepositoryStats,
+    date_format: &str,
+) -> io::Result<()> {
+    writeln!(out, "-------------------------------------")?;
+    writeln!(out, "Branches (ahead/behind HEAD, last commit):")?;
+    for branch in &stats.branches {
+        writeln!(
+            out,
+            "\t{}\t+{}\t-{}\t{}",
+            branch.name,
+            branch.ahead,
+            branch.behind,
+            format_timestamp(branch.last_commit, date_format)
+        )?;
+    }
+    Ok(())
+}
+
+fn print_largest_files(out: &mut dyn Write, stats: &RepositoryStats) -> io::Result<()> {
+    writeln!(out, "-------------------------------------")?;
+    writeln!(out, "Largest files:")?;
+    for file in &stats.largest_files {
+        writeln!(out, "\t{}\t{}", format_size(file.size), file.path)?;
+    }
+    Ok(())
+}
+
+fn print_repository_size(out: &mut dyn Write, stats: &RepositoryStats) -> io::Result<()> {
+    let size = &stats.repository_size;
+    writeln!(out, "-------------------------------------")?;
+    writeln!(out, "Repository size:")?;
+    writeln!(out, "Commit objects ........ {}", size.commits)?;
+    writeln!(out, "Tree objects .......... {}", size.trees)?;
+    writeln!(out, "Blob objects .......... {}", size.blobs)?;
+    writeln!(out, "Tag objects ........... {}", size.tags)?;
+    writeln!(
+        out,
+        "Packed size ........... {}",
+        format_size(size.packed_size)
+    )?;
+    writeln!(
+        out,
+        "Size on disk .......... {}",
+        format_size(size.git_dir_size)
+    )?;
+    Ok(())
+}
+
+/// Formats an amount of bytes with binary units, e.g. "1.5 MiB"
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+fn print_growth_stats(out: &mut dyn Write, stats: &RepositoryStats) -> io::Result<()> {
+    writeln!(out, "-------------------------------------")?;
+    writeln!(out, "Contributors per month:")?;
+    writeln!(out, "\tMonth\tActive\tTotal")?;
+    for month in &stats.contributor_growth {
+        writeln!(
+            out,
+            "\t{}-{:02}\t{}\t{}",
+            month.year, month.month, month.active_contributors, month.total_contributors
+        )?;
+    }
+    Ok(())
+}
+
+/// Prints the first and last commit of every contributor, the most recently active first
+fn print_author_timeline(
+    out: &mut dyn Write,
+    stats: &RepositoryStats,
+    date_format: &str,
+) -> io::Result<()> {
+    let mut authors: Vec<(&String, &(u64, u64))> = stats.author_first_last.iter().collect();
+    authors.sort_by(|a, b| b.1 .1.cmp(&a.1 .1).then(a.0.cmp(b.0)));
+
+    writeln!(out, "-------------------------------------")?;
+    writeln!(out, "Author timeline (first commit, last commit, span):")?;
+    for (author, (first, last)) in authors {
+        writeln!(
+            out,
+            "\t{}\t{}\t{}\t{} days{}",
+            author,
+            format_timestamp(*first, date_format),
+            format_timestamp(*last, date_format),
+            (last - first) / 86_400,
+            if is_active(stats, *last) {
+                "\tactive"
+            } else {
+                ""
+            }
+        )?;
+    }
+    Ok(())
+}
+
+/// Core and occasional contributors, with the commits credited to them
+#[derive(Debug, Default, PartialEq)]
+struct ContributorClasses {
+    core: usize,
+    core_commits: f64,
+    occasional: usize,
+    occasional_commits: f64,
+}
+
+/// Splits the contributors into core ones with at least the minimum of commits or a commit in the
+/// [`CORE_ACTIVE_DAYS`] before the analysis, and occasional (drive-by) ones
+fn contributor_classes(stats: &RepositoryStats) -> ContributorClasses {
+    let mut classes = ContributorClasses::default();
+    for (name, commits) in &stats.contributors {
+        let recent = stats.author_first_last.get(name).is_some_and(|(_, last)| {
+            stats.analyzed_at - (*last as i64) <= CORE_ACTIVE_DAYS * 86_400
+        });
+        if *commits >= stats.min_core_commits as f64 || recent {
+            classes.core += 1;
+            classes.core_commits += commits;
+        } else {
+            classes.occasional += 1;
+            classes.occasional_commits += commits;
+        }
+    }
+    classes
+}
+
+fn print_contributor_classes(out: &mut dyn Write, stats: &RepositoryStats) -> io::Result<()> {
+    let classes = contributor_classes(stats);
+    // Co-authors may be credited with full commits, so the shares are of all credited commits
+    let total = classes.core_commits + classes.occasional_commits;
+    let share = |commits: f64| {
+        if total > 0.0 {
+            commits / total * 100.0
+        } else {
+            0.0
+        }
+    };
+
+    writeln!(out, "-------------------------------------")?;
+    writeln!(
+        out,
+        "Contributor classes (core: at least {} commits or a commit in the last {} days):",
+        stats.min_core_commits, CORE_ACTIVE_DAYS
+    )?;
+    writeln!(
+        out,
+        "Core .................. {} ({:.1}% of commits)",
+        classes.core,
+        share(classes.core_commits)
+    )?;
+    writeln!(
+        out,
+        "Occasional ............ {} ({:.1}% of commits)",
+        classes.occasional,
+        share(classes.occasional_commits)
+    )?;
+    Ok(())
+}
+
+/// Whether the last commit was made in the [`ACTIVE_DAYS`] before the analysis
+fn is_active(stats: &RepositoryStats, last_commit: u64) -> bool {
+    stats.analyzed_at - (last_commit as i64) <= ACTIVE_DAYS * 86_400
+}
+
+fn print_hour_stats(out: &mut dyn Write, stats: &RepositoryStats, width: usize) -> io::Result<()> {
+    let bar_width = bar_width(width) as f64;
+    let max_commits = stats.commits_per_hour.iter().max().unwrap();
+
+    writeln!(out, "-------------------------------------")?;
+    writeln!(out, "Commits per hour of the day:")?;
+    if *max_commits == 0 {
+        // The bars would be scaled by zero
+        return writeln!(out, "No commits");
+    }
+    for (hour, commits) in stats.commits_per_hour.iter().enumerate() {
+        let percentage = (*commits as f64 / *max_commits as f64 * bar_width) as usize;
+        writeln!(
+            out,
+            "\t{:02}:00\t{}\t|{}",
+            hour,
+            commits,
+            "█".repeat(percentage)
+        )?;
+    }
+    Ok(())
+}
+
+/// Calculates the average length of the commit subjects
+fn average_subject_length(stats: &RepositoryStats) -> f64 {
+    if stats.commit_count == 0 {
+        return 0.0;
+    }
+    stats.total_subject_length as f64 / stats.commit_count as f64
+}
+
+/// Describes the sample the changed lines were extrapolated from, and why they are only estimates
+fn format_churn_sample((sampled, diffable): (usize, usize)) -> String {
+    format!(
+        "{} of {} commits, the changed lines are estimates (a few giant commits may be missed or overweighted)",
+        sampled, diffable
+    )
+}
+
+/// Compares the commits of the last quarter to the ones of the quarter before, e.g. "↑ 23% vs prior quarter"
+fn format_trend(stats: &RepositoryStats) -> String {
+    let history = stats.analyzed_at - stats.first_commit as i64;
+    if stats.commit_count == 0 || history < 2 * QUARTER_DAYS * 86_400 {
+        return "insufficient history".to_string();
+    }
+    let (last, prior) = (stats.commits_last_quarter, stats.commits_prior_quarter);
+    if prior == 0 {
+        return if last == 0 {
+            "no commits in the last two quarters".to_string()
+        } else {
+            format!("↑ {} commits vs none in the prior quarter", last)
+        };
+    }
+    let change = (last as f64 - prior as f64) / prior as f64 * 100.0;
+    let arrow = match last.cmp(&prior) {
+        std::cmp::Ordering::Greater => '↑',
+        std::cmp::Ordering::Less => '↓',
+        std::cmp::Ordering::Equal => '→',
+    };
+    format!("{} {:.0}% vs prior quarter", arrow, change.abs())
+}
+
+/// Formats the contributor with the most commits (the first by name on ties) and their share of all commits
+fn format_top_contributor(stats: &RepositoryStats) -> String {
+    let Some((name, commits)) = top_contributors(stats, 1, 1).into_iter().next() else {
+        return "none".to_string();
+    };
+    // Co-authors may be credited with full commits, so the share is of all credited commits
+    let total: f64 = stats.contributors.values().sum();
+    format!("{} ({:.1}%)", name, commits / total * 100.0)
+}
+
+/// Formats an amount of commits together with its share of all commits
+fn format_commit_share(stats: &RepositoryStats, commits: usize) -> String {
+    if stats.commit_count == 0 {
+        return commits.to_string();
+    }
+    let percentage = commits as f64 / stats.commit_count as f64 * 100.0;
+    format!("{} ({:.1}%)", commits, percentage)
+}
+
+fn print_message_stats(out: &mut dyn Write, stats: &RepositoryStats) -> io::Result<()> {
+    writeln!(out, "-------------------------------------")?;
+    writeln!(out, "Commit message stats:")?;
+    writeln!(
+        out,
+        "Avg. subject length ... {:.1}",
+        average_subject_length(stats)
+    )?;
+    writeln!(
+        out,
+        "Lines over {} chars ... {}",
+        MAX_MESSAGE_LINE_LENGTH,
+        format_commit_share(stats, stats.long_messages)
+    )?;
+    writeln!(
+        out,
+        "Conventional commits .. {}",
+        format_commit_share(stats, stats.conventional_commits)
+    )?;
+    writeln!(
+        out,
+        "Reverts ............... {}",
+        format_commit_share(stats, stats.revert_commits)
+    )?;
+    writeln!(
+        out,
+        "Fixups/squashes ....... {}",
+        format_commit_share(stats, stats.fixup_commits)
+    )?;
+    Ok(())
+}
+
+fn print_size_histogram(
+    out: &mut dyn Write,
+    stats: &RepositoryStats,
+    width: usize,
+) -> io::Result<()> {
+    const BUCKETS: [&str; 5] = ["0", "1-10", "11-100", "101-1000", "1000+"];
+    let bar_width = bar_width(width);
+    let max_commits = stats.commit_size_buckets.iter().max().copied().unwrap_or(0);
+
+    writeln!(out, "-------------------------------------")?;
+    writeln!(out, "Commits by changed lines:")?;
+    for (bucket, commits) in BUCKETS.iter().zip(stats.commit_size_buckets) {
+        let percentage = (commits * bar_width).checked_div(max_commits).unwrap_or(0);
+        writeln!(
+            out,
+            "\t{}\t{}\t|{}",
+            bucket,
+            commits,
+            "█".repeat(percentage)
+        )?;
+    }
+    Ok(())
+}
+
+fn print_signature_stats(out: &mut dyn Write, stats: &RepositoryStats) -> io::Result<()> {
+    writeln!(out, "-------------------------------------")?;
+    writeln!(out, "Signature stats:")?;
+    writeln!(
+        out,
+        "Signed commits ........ {}",
+        format_commit_share(stats, stats.signed_commits)
+    )?;
+    Ok(())
+}
+
+/// Calculates the upper borders of commits a day for each symbol of the commit checker board.
+/// The days with commits are split evenly onto the symbols after the first one, which is only used for no commits.
+fn symbol_thresholds(max_commits_a_day: usize) -> [usize; CHECKERBOARD_SYMBOL_AMOUNT] {
+    // At least one commit per symbol, so that small repositories still get a spread
+    let range_size = max_commits_a_day
+        .div_ceil(CHECKERBOARD_SYMBOL_AMOUNT - 1)
+        .max(1);
+    let mut thresholds = [0; CHECKERBOARD_SYMBOL_AMOUNT];
+    for (i, threshold) in thresholds.iter_mut().enumerate() {
+        *threshold = range_size * i;
+    }
+    thresholds
+}
+
+/// Gets the index of the symbol for the commits of a day.
+/// Days above the last border also get the last symbol.
+fn symbol_index(
+    commits_on_day: usize,
+    distribution: &[usize; CHECKERBOARD_SYMBOL_AMOUNT],
+) -> usize {
+    distribution
+        .iter()
+        .position(|threshold| commits_on_day <= *threshold)
+        .unwrap_or(CHECKERBOARD_SYMBOL_AMOUNT - 1)
+}
+
+/// Prints which symbol of the commit checker board stands for how many commits a day
+fn print_distribution_legend(
+    out: &mut dyn Write,
+    distribution: &[usize; CHECKERBOARD_SYMBOL_AMOUNT],
+    symbols: &[char; CHECKERBOARD_SYMBOL_AMOUNT],
+) -> io::Result<()> {
+    writeln!(out, "-------------------------------------")?;
+    write!(out, "Distribution: ")?;
+    write!(out, "{} = {} | ", symbols[0], distribution[0])?;
+    for i in 1..distribution.len() - 1 {
+        write!(out, "{} for <= {}, ", symbols[i], distribution[i])?;
+    }
+    // Days above the second to last border get the last symbol
+    writeln!(
+        out,
+        "{} for > {}",
+        symbols[distribution.len() - 1],
+        distribution[distribution.len() - 2]
+    )?;
+    writeln!(out)?;
+    Ok(())
+}
+
+fn calculate_day_commit_graph(
+    days: &[usize],
+    first_day: NaiveDate,
+    weekday: chrono::Weekday,
+    symbol_dist: &[usize; CHECKERBOARD_SYMBOL_AMOUNT],
+    symbols: &[char; CHECKERBOARD_SYMBOL_AMOUNT],
+) -> String {
+    // Find the first day in the buffer that falls onto the weekday
+    let offset =
+        (weekday.num_days_from_monday() + 7 - first_day.weekday().num_days_from_monday()) % 7;
+
+    // Each column is a week from Monday to Sunday, so weekdays before the first day are already in the second column
+    let mut graph_line = String::new();
+    if weekday.num_days_from_monday() < first_day.weekday().num_days_from_monday() {
+        graph_line.push_str("  ");
+    }
+    for commits_on_day in days.iter().skip(offset as usize).step_by(7) {
+        let commits_on_day = *commits_on_day;
+
+        graph_line.push(' ');
+        graph_line.push(symbols[symbol_index(commits_on_day, symbol_dist)]);
+    }
+    graph_line
+}
+
+/// Cleans up the temporary directory if the repository was cloned
+fn temp_dir_cleanup(repository: Repository, clone_dir: Option<PathBuf>) {
+    // Close the repository first, as open handles may prevent the removal (e.g. on Windows)
+    drop(repository);
+    if let Some(clone_dir) = clone_dir {
+        remove_clone_dir(&clone_dir);
+    }
+}
+
+/// Removes the directory of a remote clone, only warning on failure as the stats are not affected
+fn remove_clone_dir(clone_dir: &std::path::Path) {
+    if !clone_dir.exists() {
+        return;
+    }
+    if let Err(e) = std::fs::remove_dir_all(clone_dir) {
+        eprintln!(
+            "Warning: Failed to remove the temporary directory '{}': {}",
+            clone_dir.display(),
+            e
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use repolyzer::analyze;
+
+    #[test]
+    fn add_del_ratio_without_deletions() {
+        let mut stats = RepositoryStats::default();
+        assert_eq!(format_add_del_ratio(&stats), "n/a");
+
+        stats.total_lines_inserted = 42;
+        assert_eq!(format_add_del_ratio(&stats), "∞ (no deletions)");
+
+        stats.total_lines_removed = 8;
+        assert_eq!(format_add_del_ratio(&stats), "5.25");
+    }
+
+    #[test]
+    fn interval_formatting() {
+        assert_eq!(format_interval(None), "n/a");
+        assert_eq!(format_interval(Some(42)), "42s");
+        assert_eq!(format_interval(Some(3_600 + 120)), "1h 2m");
+        assert_eq!(format_interval(Some(2 * 86_400 + 4 * 3_600 + 59)), "2d 4h");
+    }
+
+    #[test]
+    fn weekday_stats_without_commits() {
+        let stats = RepositoryStats::default();
+        assert_eq!(stats.commits_per_weekday, [0; 7]);
+        for normalize in [false, true] {
+            let mut out = Vec::new();
+            print_weekday_stats(&mut out, &stats, normalize, DEFAULT_WIDTH).unwrap();
+            let out = String::from_utf8(out).unwrap();
+            assert!(out.ends_with("No commits\n"));
+            assert!(!out.contains('█'));
+        }
+    }
+
+    #[test]
+    fn hour_stats_without_commits() {
+        let stats = RepositoryStats::default();
+        let mut out = Vec::new();
+        print_hour_stats(&mut out, &stats, DEFAULT_WIDTH).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.ends_with("Commits per hour of the day:\nNo commits\n"));
+    }
+
+    #[test]
+    fn weekday_occurrences_in_window() {
+        let mut stats = RepositoryStats::default();
+        assert_eq!(weekday_occurrences(&stats), [0; 7]);
+
+        // From Monday, 2024-01-01 to Wednesday, 2024-01-10
+        let day = |day| {
+            NaiveDate::from_ymd_opt(2024, 1, day)
+                .unwrap()
+                .num_days_from_ce()
+        };
+        stats.weekday_window = Some((day(1), day(10)));
+        assert_eq!(weekday_occurrences(&stats), [2, 2, 2, 1, 1, 1, 1]);
+    }
+
+    #[test]
+    fn active_authors() {
+        let stats = RepositoryStats {
+            analyzed_at: 100 * 86_400,
+            ..RepositoryStats::default()
+        };
+        assert!(is_active(&stats, 10 * 86_400));
+        assert!(!is_active(&stats, 10 * 86_400 - 1));
+    }
+
+    #[test]
+    fn repository_list() {
+        let content = "# Services\n/srv/api\n\n  https://example.com/web.git  \n\t# Libraries\ngit@example.com:org/lib.git\n";
+        assert_eq!(
+            parse_repository_list(content),
+            [
+                "/srv/api",
+                "https://example.com/web.git",
+                "git@example.com:org/lib.git"
+            ]
+        );
+    }
+
+    #[test]
+    fn top_contributor_formatting() {
+        let mut stats = RepositoryStats::default();
+        assert_eq!(format_top_contributor(&stats), "none");
+
+        stats.contributors = HashMap::from([
+            ("Bob".to_string(), 3.0),
+            ("Alice".to_string(), 3.0),
+            ("Carol".to_string(), 2.0),
+        ]);
+        // Ties go to the first name
+        assert_eq!(format_top_contributor(&stats), "Alice (37.5%)");
+    }
+
+    #[test]
+    fn trend_formatting() {
+        const QUARTER: i64 = QUARTER_DAYS * 86_400;
+        let mut stats = RepositoryStats {
+            analyzed_at: 10 * QUARTER,
+            first_commit: (9 * QUARTER) as u64,
+            commit_count: 10,
+            commits_last_quarter: 10,
+            ..RepositoryStats::default()
+        };
+        assert_eq!(format_trend(&stats), "insufficient history");
+
+        stats.first_commit = 0;
+        assert_eq!(
+            format_trend(&stats),
+            "↑ 10 commits vs none in the prior quarter"
+        );
+        stats.commits_prior_quarter = 8;
+        assert_eq!(format_trend(&stats), "↑ 25% vs prior quarter");
+        stats.commits_last_quarter = 4;
+        assert_eq!(format_trend(&stats), "↓ 50% vs prior quarter");
+        stats.commits_last_quarter = 8;
+        assert_eq!(format_trend(&stats), "→ 0% vs prior quarter");
+    }
+
+    #[test]
+    fn size_formatting() {
+        assert_eq!(format_size(0), "0 B");
+        assert_eq!(format_size(1023), "1023 B");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(5 * 1024 * 1024), "5.0 MiB");
+    }
+
+    #[test]
+    fn timestamp_formatting() {
+        // 2021-03-04 05:06:07 UTC
+        let timestamp = 1_614_834_367;
+        assert_eq!(
+            format_timestamp(timestamp, DEFAULT_DATE_FORMAT),
+            "2021-03-04 05:06:07"
+        );
+        assert_eq!(format_timestamp(timestamp, "%m/%d/%Y"), "03/04/2021");
+    }
+
+    #[test]
+    fn bus_factor_and_gini_coefficient() {
+        let mut stats = RepositoryStats::default();
+        assert_eq!(bus_factor(&stats), 0);
+        assert_eq!(gini_coefficient(&stats), 0.0);
+
+        stats.contributors.insert("Alice".into(), 10.0);
+        assert_eq!(bus_factor(&stats), 1);
+        assert_eq!(gini_coefficient(&stats), 0.0);
+
+        // Exactly half of the commits are not enough
+        stats.contributors.insert("Bob".into(), 10.0);
+        assert_eq!(bus_factor(&stats), 2);
+        assert_eq!(gini_coefficient(&stats), 0.0);
+
+        stats.contributors.insert("Carol".into(), 0.0);
+        stats.contributors.insert("Dave".into(), 0.0);
+        assert!((gini_coefficient(&stats) - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn symbol_thresholds_without_commits() {
+        assert_eq!(symbol_thresholds(0), [0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn symbol_thresholds_for_few_commits() {
+        // Every commit amount gets its own symbol instead of all collapsing onto the last one
+        assert_eq!(symbol_thresholds(3), [0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn symbol_thresholds_for_many_commits() {
+        assert_eq!(symbol_thresholds(100), [0, 25, 50, 75, 100]);
+    }
+
+    #[test]
+    fn checker_board_fits_width() {
+        let first_day = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        assert!(month_header(first_day, 53, 0).starts_with("        Jan       Feb     Mar"));
+
+        // Only the last 16 of the 53 weeks fit into 40 columns
+        let mut out = Vec::new();
+        let symbols = Theme::Ascii.symbols();
+        print_checker_board_rows(&mut out, &[0; 366], first_day, &[0; 5], &symbols, 40).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let mut lines = out.lines();
+        let header = lines.next().unwrap();
+        assert!(!header.contains("Jan") && header.ends_with("Dec"));
+        assert!(header.chars().count() <= 40);
+        let monday = lines.next().unwrap();
+        assert_eq!(monday, format!("║ Mon\t{}", " .".repeat(16)));
+    }
+
+    #[test]
+    fn bar_width_of_narrow_output() {
+        assert_eq!(bar_width(DEFAULT_WIDTH), 55);
+        assert_eq!(bar_width(20), 10);
+    }
+
+    #[test]
+    fn day_commit_graph_aligns_weekdays() {
+        let distribution = symbol_thresholds(1);
+        // Wednesday, so that the first Monday is only in the second week
+        let first_day = NaiveDate::from_ymd_opt(2024, 1, 3).unwrap();
+        let mut days = [0; 14];
+        // Monday 2024-01-08
+        days[5] = 1;
+
+        let graph = |weekday, theme: Theme| {
+            calculate_day_commit_graph(&days, first_day, weekday, &distribution, &theme.symbols())
+        };
+        assert_eq!(graph(chrono::Weekday::Mon, Theme::Unicode), "   · ~");
+        assert_eq!(graph(chrono::Weekday::Tue, Theme::Unicode), "   ~ ~");
+        assert_eq!(graph(chrono::Weekday::Wed, Theme::Unicode), " ~ ~");
+        assert_eq!(graph(chrono::Weekday::Sun, Theme::Unicode), " ~ ~");
+        // Other themes only differ in the symbols
+        assert_eq!(graph(chrono::Weekday::Mon, Theme::Ascii), "   : .");
+        assert_eq!(graph(chrono::Weekday::Mon, Theme::Blocks), "   ░ ·");
+    }
+
+    /// Creates a bare repository with a commit per content of its single file (repeating one makes an empty commit)
+    fn bare_repository(contents: &[&str]) -> (tempfile::TempDir, Repository) {
+        let dir = tempfile::tempdir().unwrap();
+        let repository = Repository::init_bare(dir.path()).unwrap();
+        let signature = git2::Signature::now("A", "a@example.com").unwrap();
+        let mut parent: Option<git2::Oid> = None;
+        for content in contents {
+            let blob = repository.blob(content.as_bytes()).unwrap();
+            let mut tree = repository.treebuilder(None).unwrap();
+            tree.insert("file.txt", blob, 0o100644).unwrap();
+            let tree = repository.find_tree(tree.write().unwrap()).unwrap();
+            let parent_commit = parent.map(|id| repository.find_commit(id).unwrap());
+            let parents: Vec<&git2::Commit> = parent_commit.iter().collect();
+            parent = Some(
+                repository
+                    .commit(
+                        Some("HEAD"),
+                        &signature,
+                        &signature,
+                        "Commit",
+                        &tree,
+                        &parents,
+                    )
+                    .unwrap(),
+            );
+        }
+        (dir, repository)
+    }
+
+    #[test]
+    fn analyze_bare_repository() {
+        let (dir, _) = bare_repository(&["first\n", "first\nsecond\n"]);
+        let location = GitLocation::Local(dir.path().to_path_buf());
+        let (repository, clone_dir) = load_repository(&location, &AppArgs::new()).unwrap();
+        assert!(repository.is_bare());
+        assert!(clone_dir.is_none());
+
+        let opts = AnalysisOptions {
+            extended_stats: true,
+            ..AnalysisOptions::default()
+        };
+        let stats = analyze(&repository, &opts).unwrap();
+        assert_eq!(stats.commit_count, 2);
+        assert_eq!(stats.total_lines_inserted, 1);
+    }
+
+    #[test]
+    fn cache_key_of_later_day() {
+        let dir = tempfile::tempdir().unwrap();
+        let repository = Repository::init_bare(dir.path()).unwrap();
+        let day1 = DETERMINISTIC_NOW + 12 * 3600;
+        let mut parent: Option<git2::Commit> = None;
+        for days_ago in [200, 10] {
+            let time = git2::Time::new(day1 - days_ago * 86_400, 0);
+            let signature = git2::Signature::new("A", "a@example.com", &time).unwrap();
+            let tree = repository.treebuilder(None).unwrap().write().unwrap();
+            let tree = repository.find_tree(tree).unwrap();
+            let parents: Vec<&git2::Commit> = parent.iter().collect();
+            let commit_id = repository
+                .commit(
+                    Some("HEAD"),
+                    &signature,
+                    &signature,
+                    "Commit",
+                    &tree,
+                    &parents,
+                )
+                .unwrap();
+            parent = Some(repository.find_commit(commit_id).unwrap());
+        }
+        let location = GitLocation::Local(dir.path().to_path_buf());
+        let opts_at = |now| AnalysisOptions {
+            fixed_now: Some(now),
+            ..AnalysisOptions::default()
+        };
+
+        // A quarter later, the last commit moved into the prior quarter
+        let day2 = day1 + 100 * 86_400;
+        let trend = |now| format_trend(&analyze(&repository, &opts_at(now)).unwrap());
+        assert_ne!(trend(day1), trend(day2));
+        let key = |now| cache_key(&repository, &location, &opts_at(now)).unwrap();
+        assert_ne!(key(day1), key(day2));
+        // A fixed current time is keyed exactly, the real one by its date
+        assert_ne!(key(day1), key(day1 + 60));
+    }
+
+    #[test]
+    fn summary_of_empty_commits() {
+        let (_first_dir, first) = bare_repository(&["a\n", "a\n"]);
+        let (_second_dir, second) = bare_repository(&["b\n", "b\n"]);
+        let summary = |no_empty| {
+            let opts = AnalysisOptions {
+                extended_stats: true,
+                no_empty,
+                ..AnalysisOptions::default()
+            };
+            let all_stats = [
+                analyze(&first, &opts).unwrap(),
+                analyze(&second, &opts).unwrap(),
+            ];
+            aggregate_stats(all_stats.iter())
+        };
+        assert_eq!(format_empty_commits(&summary(false)), "2 (50.0%)");
+        assert_eq!(format_empty_commits(&summary(true)), "excluded");
+    }
+
+    #[test]
+    fn summary_keeps_overview_rows() {
+        let (_dir, repository) = bare_repository(&["a\n", "a\n", "b\n"]);
+        let opts = AnalysisOptions {
+            extended_stats: true,
+            no_merges: true,
+            first_parent: true,
+            identity: Identity::Committer,
+            author: Some("A".to_string()),
+            exclude_authors: vec!["B".to_string()],
+            paths: vec!["file.txt".to_string()],
+            limit: Some(10),
+            sample_rate: Some(1.0),
+            diff_algorithm: DiffAlgorithm::Patience,
+            ..AnalysisOptions::default()
+        };
+        let mut stats = analyze(&repository, &opts).unwrap();
+        stats.analyzed_branch = None;
+        stats.binary_files_changed = 1;
+
+        // Apart from the commit intervals, a summary of a single repository is the repository itself
+        let rows = overview_rows(&stats, true, DEFAULT_DATE_FORMAT)
+            .into_iter()
+            .filter(|(label, _)| !label.contains("interval"))
+            .collect::<Vec<_>>();
+        let summary = aggregate_stats(std::iter::once(&stats));
+        assert_eq!(overview_rows(&summary, true, DEFAULT_DATE_FORMAT), rows);
+    }
+
+    #[test]
+    fn summary_without_commit_intervals() {
+        let (_dir, repository) = bare_repository(&["a\n", "b\n"]);
+        let opts = AnalysisOptions {
+            extended_stats: true,
+            ..AnalysisOptions::default()
+        };
+        let stats = analyze(&repository, &opts).unwrap();
+        let labels = |stats: &RepositoryStats| -> Vec<&str> {
+            overview_rows(stats, true, DEFAULT_DATE_FORMAT)
+                .into_iter()
+                .map(|(label, _)| label)
+                .collect()
+        };
+        assert!(labels(&stats).contains(&"Median interval ......."));
+        let summary = aggregate_stats(std::iter::once(&stats));
+        assert!(!labels(&summary).contains(&"Average interval ......"));
+        assert!(!labels(&summary).contains(&"Median interval ......."));
+        assert!(serialize_stats(&summary)
+            .get("average_commit_interval")
+            .is_none());
+    }
+
+    #[test]
+    fn create_temp_dir_is_unique() {
+        let first = create_temp_dir();
+        let second = create_temp_dir();
+        assert_ne!(first, second);
+        assert_eq!(first.parent(), second.parent());
+    }
+
+    #[test]
+    fn pie_chart_data_with_many_contributors() {
+        let mut stats = RepositoryStats::default();
+        for i in 0..20 {
+            stats
+                .contributors
+                .insert(format!("Author {}", i), (i + 1) as f64);
+        }
+
+        let data = pie_chart_data(&top_contributors(&stats, 15, 1));
+        assert_eq!(data.len(), 16);
+        assert_eq!(data[0].label, "Author 19");
+        assert_eq!(data[15].label, "Others");
+        assert_eq!(data[15].value, 15.0);
+
+        // Contributors below the threshold are part of "Others", even if fewer than 15 are named
+        let frequent_contributors = top_contributors(&stats, 15, 10);
+        assert_eq!(frequent_contributors.len(), 12);
+        assert_eq!(frequent_contributors[11], ("Others".to_string(), 45.0));
+        let listed = contributor_list(&stats, 15, 10);
+        assert_eq!(listed[11].0, "9 other contributors");
+    }
+
+    #[test]
+    fn export_file_names() {
+        let mut app_args = AppArgs::new();
+        app_args.exports = vec![OutputFormat::Html, OutputFormat::Json];
+        assert!(export_paths(&app_args).is_empty());
+
+        app_args.output_dir = Some(PathBuf::from("out"));
+        let paths: Vec<PathBuf> = export_paths(&app_args)
+            .into_iter()
+            .map(|(_, path)| path)
+            .collect();
+        assert_eq!(
+            paths,
+            [
+                PathBuf::from("out/report.html"),
+                PathBuf::from("out/stats.json")
+            ]
+        );
+    }
+
+    #[test]
+    fn contributor_class_split() {
+        let now = 1_704_067_200;
+        let stats = RepositoryStats {
+            analyzed_at: now,
+            min_core_commits: 10,
+            contributors: HashMap::from([
+                ("Ada".to_string(), 12.0),
+                ("Bob".to_string(), 2.0),
+                ("Eve".to_string(), 1.0),
+            ]),
+            author_first_last: HashMap::from([
+                ("Ada".to_string(), (0, 0)),
+                // Recently active, despite only two commits
+                ("Bob".to_string(), (0, (now - 30 * 86_400) as u64)),
+                ("Eve".to_string(), (0, (now - 200 * 86_400) as u64)),
+            ]),
+            ..RepositoryStats::default()
+        };
+        assert_eq!(
+            contributor_classes(&stats),
+            ContributorClasses {
+                core: 2,
+                core_commits: 14.0,
+                occasional: 1,
+                occasional_commits: 1.0,
+            }
+        );
+    }
+
+    #[test]
+    fn json_schema_version() {
+        let location = GitLocation::Local(PathBuf::from("."));
+        let result = RepositoryResult {
+            location: &location,
+            stats: RepositoryStats::default(),
+            has_no_commits: true,
+            submodules: vec![SubmoduleResult {
+                path: "vendor/lib".to_string(),
+                stats: RepositoryStats::default(),
+            }],
+        };
+        let json = serialize_result(&result);
+        assert_eq!(json["schema_version"], JSON_SCHEMA_VERSION);
+        // Only the top-level object is versioned
+        assert!(json["submodules"][0].get("schema_version").is_none());
+        assert!(json["total_with_submodules"]
+            .get("schema_version")
+            .is_none());
+    }
+
+    #[test]
+    fn print_stats_without_commits() {
+        let stats = RepositoryStats {
+            has_extended_stats: true,
+            has_commit_graph: true,
+            has_all_time_heatmap: true,
+            has_weekday_stats: true,
+            has_hour_stats: true,
+            has_message_stats: true,
+            has_signature_stats: true,
+            has_lang_stats: true,
+            has_dir_stats: true,
+            has_size_histogram: true,
+            has_hotspots: true,
+            has_coupling: true,
+            has_tag_stats: true,
+            has_branch_stats: true,
+            has_growth_stats: true,
+            has_author_timeline: true,
+            has_contributor_classes: true,
+            has_largest_files: true,
+            has_repository_size: true,
+            ..RepositoryStats::default()
+        };
+
+        // None of the printers may panic without any commits
+        let out = &mut std::io::sink();
+        print_overview(out, &stats, true, DEFAULT_DATE_FORMAT).unwrap();
+        print_markdown_overview(out, &stats, true, DEFAULT_DATE_FORMAT).unwrap();
+        print_contributors(out, &stats, 5, 1).unwrap();
+        print_markdown_contributors(out, &stats, 5, 1).unwrap();
+        print_pie_chart(out, &stats, 5, 1, false).unwrap();
+        print_commit_checker_board(out, &stats, &Theme::Unicode.symbols(), DEFAULT_WIDTH).unwrap();
+        print_all_time_heatmap(out, &stats, &Theme::Unicode.symbols(), DEFAULT_WIDTH).unwrap();
+        print_weekday_stats(out, &stats, false, DEFAULT_WIDTH).unwrap();
+        print_weekday_stats(out, &stats, true, DEFAULT_WIDTH).unwrap();
+        print_hour_stats(out, &stats, DEFAULT_WIDTH).unwrap();
+        print_message_stats(out, &stats).unwrap();
+        print_signature_stats(out, &stats).unwrap();
+        print_lang_stats(out, &stats).unwrap();
+        print_dir_stats(out, &stats).unwrap();
+        print_size_histogram(out, &stats, DEFAULT_WIDTH).unwrap();
+        print_hotspots(out, &stats).unwrap();
+        print_coupling(out, &stats).unwrap();
+        print_tag_stats(out, &stats, DEFAULT_DATE_FORMAT).unwrap();
+        print_branch_stats(out, &stats, DEFAULT_DATE_FORMAT).unwrap();
+        print_growth_stats(out, &stats).unwrap();
+        print_author_timeline(out, &stats, DEFAULT_DATE_FORMAT).unwrap();
+        print_contributor_classes(out, &stats).unwrap();
+        print_largest_files(out, &stats).unwrap();
+        print_repository_size(out, &stats).unwrap();
+        html::print_overview(out, &stats, true, DEFAULT_DATE_FORMAT).unwrap();
+        html::print_pie_chart(out, &stats, 5, 1).unwrap();
+        html::print_commit_graph(out, &stats).unwrap();
+        html::print_all_time_heatmap(out, &stats).unwrap();
+        serialize_stats(&stats);
+    }
+
+    #[test]
+    fn markdown_code_block_from_text_section() {
+        let mut out: Vec<u8> = Vec::new();
+        print_markdown_code_block(&mut out, |out| {
+            writeln!(out, "-------------------------------------")?;
+            writeln!(out, "Commits per week day:")?;
+            writeln!(out, "\tMonday: 3")?;
+            writeln!(out, "-------------------------------------")
+        })
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "### Commits per week day\n\n```text\n\tMonday: 3\n```\n\n"
+        );
+    }
+}
@@ -0,0 +1,662 @@
+mod common;
+
+use common::{commit_file, commit_files, init_repository, ALICE, BOB};
+use git2::{ObjectType, Repository};
+use repolyzer::{analyze, AnalysisOptions, ChurnMerges, DiffAlgorithm, Identity};
+
+// 2024-01-01 10:00:00 UTC, a Monday
+const MONDAY: i64 = 1_704_103_200;
+const DAY: i64 = 86_400;
+
+/// Builds the history shared by the tests:
+/// Alice adds the README on Monday, Bob adds the code on Wednesday and Alice changes a README line on Saturday
+fn fixture(repository: &Repository) {
+    commit_file(repository, "README.md", "a\nb\n", &ALICE, MONDAY);
+    commit_file(
+        repository,
+        "src/main.rs",
+        "fn main() {\n}\n// end\n",
+        &BOB,
+        MONDAY + 2 * DAY,
+    );
+    commit_file(repository, "README.md", "a\nc\n", &ALICE, MONDAY + 5 * DAY);
+}
+
+#[test]
+fn general_stats() {
+    let (_dir, repository) = init_repository();
+    fixture(&repository);
+
+    let stats = analyze(&repository, &AnalysisOptions::default()).unwrap();
+    assert_eq!(stats.commit_count, 3);
+    assert_eq!(stats.merge_commits, 0);
+    assert_eq!(stats.first_commit, MONDAY as u64);
+    assert_eq!(stats.last_commit, (MONDAY + 5 * DAY) as u64);
+    assert_eq!(stats.contributors.len(), 2);
+    assert_eq!(stats.contributors["Alice"], 2.0);
+    assert_eq!(stats.contributors["Bob"], 1.0);
+}
+
+#[test]
+fn changed_lines() {
+    let (_dir, repository) = init_repository();
+    fixture(&repository);
+
+    let opts = AnalysisOptions {
+        extended_stats: true,
+        ..AnalysisOptions::default()
+    };
+    let stats = analyze(&repository, &opts).unwrap();
+    // The root commit has no parent to diff against, so only the later two commits count
+    assert_eq!(stats.total_files_changes, 2);
+    assert_eq!(stats.total_lines_inserted, 4);
+    assert_eq!(stats.total_lines_removed, 1);
+    assert_eq!(stats.lines_per_author["Alice"], (1, 1));
+    assert_eq!(stats.lines_per_author["Bob"], (3, 0));
+}
+
+#[test]
+fn weekday_buckets() {
+    let (_dir, repository) = init_repository();
+    fixture(&repository);
+
+    let opts = AnalysisOptions {
+        weekday_stats: true,
+        ..AnalysisOptions::default()
+    };
+    let stats = analyze(&repository, &opts).unwrap();
+    // Monday, Wednesday and Saturday
+    assert_eq!(stats.commits_per_weekday, [1, 0, 1, 0, 0, 1, 0]);
+}
+
+#[test]
+fn authors_without_name_are_counted_by_email() {
+    let (_dir, repository) = init_repository();
+    commit_file(&repository, "README.md", "a\n", &ALICE, MONDAY);
+
+    // git2 refuses to create signatures with an empty name, so the commit is written by hand
+    let parent = repository.head().unwrap().peel_to_commit().unwrap();
+    let raw_commit = format!(
+        "tree {}\nparent {}\nauthor  <nameless@example.com> {} +0000\ncommitter  <nameless@example.com> {} +0000\n\nCommit\n",
+        parent.tree_id(),
+        parent.id(),
+        MONDAY + DAY,
+        MONDAY + DAY
+    );
+    let commit_id = repository
+        .odb()
+        .unwrap()
+        .write(ObjectType::Commit, raw_commit.as_bytes())
+        .unwrap();
+    repository
+        .reference("refs/heads/master", commit_id, true, "Nameless commit")
+        .unwrap();
+    repository.set_head("refs/heads/master").unwrap();
+
+    let stats = analyze(&repository, &AnalysisOptions::default()).unwrap();
+    assert_eq!(stats.commit_count, 2);
+    assert_eq!(stats.contributors["nameless@example.com"], 1.0);
+    assert!(!stats.contributors.contains_key(">UNKNOWN<"));
+}
+
+#[test]
+fn authors_with_latin1_names() {
+    let (_dir, repository) = init_repository();
+    commit_file(&repository, "README.md", "a\n", &ALICE, MONDAY);
+
+    // Old repositories may contain names in Latin-1 instead of UTF-8, which git2 only hands out as bytes
+    let parent = repository.head().unwrap().peel_to_commit().unwrap();
+    let mut raw_commit = format!(
+        "tree {}\nparent {}\nauthor Jos",
+        parent.tree_id(),
+        parent.id()
+    )
+    .into_bytes();
+    raw_commit.push(0xE9);
+    raw_commit.extend(
+        format!(
+            " <jose@example.com> {} +0000\ncommitter Alice <alice@example.com> {} +0000\n\nCommit\n",
+            MONDAY + DAY,
+            MONDAY + DAY
+        )
+        .bytes(),
+    );
+    let commit_id = repository
+        .odb()
+        .unwrap()
+        .write(ObjectType::Commit, &raw_commit)
+        .unwrap();
+    repository
+        .reference("refs/heads/master", commit_id, true, "Latin-1 commit")
+        .unwrap();
+
+    let opts = AnalysisOptions {
+        author: Some("jos".to_string()),
+        ..AnalysisOptions::default()
+    };
+    let stats = analyze(&repository, &opts).unwrap();
+    // The invalid byte is replaced, but the rest of the name is kept
+    assert_eq!(stats.commit_count, 1);
+    assert_eq!(stats.contributors["Jos\u{FFFD}"], 1.0);
+}
+
+#[test]
+fn contributors_by_committer() {
+    let (_dir, repository) = init_repository();
+    fixture(&repository);
+    // Bob rebased a change of Alice
+    commit_files(
+        &repository,
+        &[("README.md", "a\nc\nd\n")],
+        &ALICE,
+        &BOB,
+        MONDAY + 6 * DAY,
+        "Commit",
+    );
+
+    let opts = AnalysisOptions {
+        extended_stats: true,
+        ..AnalysisOptions::default()
+    };
+    let stats = analyze(&repository, &opts).unwrap();
+    assert_eq!(stats.contributors["Alice"], 3.0);
+    assert_eq!(stats.contributors["Bob"], 1.0);
+
+    let opts = AnalysisOptions {
+        identity: Identity::Committer,
+        ..opts
+    };
+    let stats = analyze(&repository, &opts).unwrap();
+    assert_eq!(stats.contributor_identity, Identity::Committer);
+    assert_eq!(stats.contributors["Alice"], 2.0);
+    assert_eq!(stats.contributors["Bob"], 2.0);
+    assert_eq!(stats.lines_per_author["Bob"], (4, 0));
+}
+
+#[test]
+fn signed_commits() {
+    let (_dir, repository) = init_repository();
+    fixture(&repository);
+
+    // Sign a commit on top, the signature itself doesn't have to be valid
+    let parent = repository.head().unwrap().peel_to_commit().unwrap();
+    let signature = git2::Signature::new(
+        ALICE.name,
+        ALICE.email,
+        &git2::Time::new(MONDAY + 6 * DAY, 0),
+    )
+    .unwrap();
+    let buffer = repository
+        .commit_create_buffer(
+            &signature,
+            &signature,
+            "Signed",
+            &parent.tree().unwrap(),
+            &[&parent],
+        )
+        .unwrap();
+    let commit_id = repository
+        .commit_signed(
+            std::str::from_utf8(&buffer).unwrap(),
+            "-----BEGIN PGP SIGNATURE-----\n\nabc\n-----END PGP SIGNATURE-----",
+            None,
+        )
+        .unwrap();
+    let head = repository.head().unwrap();
+    repository
+        .reference(head.name().unwrap(), commit_id, true, "Signed commit")
+        .unwrap();
+
+    let opts = AnalysisOptions {
+        signature_stats: true,
+        ..AnalysisOptions::default()
+    };
+    let stats = analyze(&repository, &opts).unwrap();
+    assert_eq!(stats.commit_count, 4);
+    assert_eq!(stats.signed_commits, 1);
+}
+
+#[test]
+fn commit_size_histogram() {
+    let (_dir, repository) = init_repository();
+    fixture(&repository);
+    let big_file = "line\n".repeat(150);
+    commit_file(&repository, "big.txt", &big_file, &BOB, MONDAY + 6 * DAY);
+
+    let opts = AnalysisOptions {
+        size_histogram: true,
+        ..AnalysisOptions::default()
+    };
+    let stats = analyze(&repository, &opts).unwrap();
+    // The root commit isn't diffed, so it has no changed lines
+    assert_eq!(stats.commit_size_buckets, [1, 2, 0, 1, 0]);
+}
+
+#[test]
+fn first_parent_history() {
+    let (_dir, repository) = init_repository();
+    let root = commit_file(&repository, "README.md", "a\n", &ALICE, MONDAY);
+    let root = repository.find_commit(root).unwrap();
+
+    // Bob works on a branch, while Alice continues on the mainline and then merges the branch
+    let time = |days| git2::Time::new(MONDAY + days * DAY, 0);
+    let bob = git2::Signature::new(BOB.name, BOB.email, &time(1)).unwrap();
+    let side = repository
+        .commit(None, &bob, &bob, "Side", &root.tree().unwrap(), &[&root])
+        .unwrap();
+    let side = repository.find_commit(side).unwrap();
+    let main = commit_file(&repository, "README.md", "b\n", &ALICE, MONDAY + 2 * DAY);
+    let main = repository.find_commit(main).unwrap();
+    let alice = git2::Signature::new(ALICE.name, ALICE.email, &time(3)).unwrap();
+    repository
+        .commit(
+            Some("HEAD"),
+            &alice,
+            &alice,
+            "Merge",
+            &main.tree().unwrap(),
+            &[&main, &side],
+        )
+        .unwrap();
+
+    let stats = analyze(&repository, &AnalysisOptions::default()).unwrap();
+    assert_eq!(stats.commit_count, 4);
+    assert_eq!(stats.contributors.len(), 2);
+
+    let opts = AnalysisOptions {
+        first_parent: true,
+        ..AnalysisOptions::default()
+    };
+    let stats = analyze(&repository, &opts).unwrap();
+    assert!(stats.first_parent_only);
+    assert_eq!(stats.commit_count, 3);
+    assert_eq!(stats.merge_commits, 1);
+    assert!(!stats.contributors.contains_key("Bob"));
+
+    // The limit keeps the most recent mainline commits
+    let opts = AnalysisOptions {
+        limit: Some(2),
+        ..opts
+    };
+    let stats = analyze(&repository, &opts).unwrap();
+    assert_eq!(stats.commit_count, 2);
+    assert_eq!(stats.first_commit, (MONDAY + 2 * DAY) as u64);
+}
+
+#[test]
+fn largest_files_of_head() {
+    let (_dir, repository) = init_repository();
+    fixture(&repository);
+    commit_file(
+        &repository,
+        "docs/big.md",
+        &"x".repeat(100),
+        &BOB,
+        MONDAY + 6 * DAY,
+    );
+
+    let opts = AnalysisOptions {
+        largest_files: Some(2),
+        ..AnalysisOptions::default()
+    };
+    let stats = analyze(&repository, &opts).unwrap();
+    let files: Vec<(&str, u64)> = stats
+        .largest_files
+        .iter()
+        .map(|file| (file.path.as_str(), file.size))
+        .collect();
+    assert_eq!(files, [("docs/big.md", 100), ("src/main.rs", 21)]);
+}
+
+#[test]
+fn repository_objects() {
+    let (_dir, repository) = init_repository();
+    fixture(&repository);
+
+    let opts = AnalysisOptions {
+        repo_size: true,
+        ..AnalysisOptions::default()
+    };
+    let size = analyze(&repository, &opts).unwrap().repository_size;
+    // Three root trees and the unchanged src/ tree, two versions of the README and main.rs
+    assert_eq!(
+        (size.commits, size.trees, size.blobs, size.tags),
+        (3, 4, 3, 0)
+    );
+    // Nothing is packed yet, but the loose objects take up space
+    assert_eq!(size.packed_size, 0);
+    assert!(size.git_dir_size > 0);
+}
+
+#[test]
+fn hotspots() {
+    let (_dir, repository) = init_repository();
+    fixture(&repository);
+    commit_file(&repository, "README.md", "a\nd\n", &BOB, MONDAY + 6 * DAY);
+
+    let opts = AnalysisOptions {
+        hotspots: true,
+        ..AnalysisOptions::default()
+    };
+    let stats = analyze(&repository, &opts).unwrap();
+    // The changes of the root commit aren't diffed
+    assert_eq!(stats.commits_per_file.len(), 2);
+    assert_eq!(stats.commits_per_file["README.md"], 2);
+    assert_eq!(stats.commits_per_file["src/main.rs"], 1);
+}
+
+#[test]
+fn coupling() {
+    let (_dir, repository) = init_repository();
+    fixture(&repository);
+    let files = |content| {
+        [
+            ("README.md", content),
+            ("src/main.rs", content),
+            ("b.txt", content),
+        ]
+    };
+    commit_files(
+        &repository,
+        &files("1\n"),
+        &ALICE,
+        &ALICE,
+        MONDAY + 6 * DAY,
+        "Change all",
+    );
+    commit_files(
+        &repository,
+        &files("2\n"),
+        &BOB,
+        &BOB,
+        MONDAY + 7 * DAY,
+        "Change all",
+    );
+    commit_file(&repository, "b.txt", "3\n", &BOB, MONDAY + 8 * DAY);
+
+    let opts = AnalysisOptions {
+        coupling: Some(2),
+        ..AnalysisOptions::default()
+    };
+    let stats = analyze(&repository, &opts).unwrap();
+    let pairs: Vec<(&str, &str, usize)> = stats
+        .coupled_files
+        .iter()
+        .map(|pair| (pair.first.as_str(), pair.second.as_str(), pair.commits))
+        .collect();
+    // All three pairs were changed together twice, so the paths decide
+    assert_eq!(
+        pairs,
+        [("README.md", "b.txt", 2), ("README.md", "src/main.rs", 2)]
+    );
+    // Only collected for the coupling
+    assert!(stats.commits_per_file.is_empty());
+}
+
+#[test]
+fn fixed_current_time() {
+    let (_dir, repository) = init_repository();
+    fixture(&repository);
+
+    let opts = AnalysisOptions {
+        commit_graph: true,
+        fixed_now: Some(MONDAY + 7 * DAY),
+        ..AnalysisOptions::default()
+    };
+    let stats = analyze(&repository, &opts).unwrap();
+    // The commits are long ago, but within the last year of the fixed time
+    assert_eq!(stats.analyzed_at, MONDAY + 7 * DAY);
+    assert_eq!(stats.commits_last_year, 3);
+    assert_eq!(stats.current_commit_streak, 0);
+    // Every day has one commit, so the most recent one is the busiest
+    let saturday = chrono::NaiveDate::from_ymd_opt(2024, 1, 6).unwrap();
+    assert_eq!(stats.busiest_day(), Some((saturday, 1)));
+    assert_eq!(
+        (stats.commits_last_quarter, stats.commits_prior_quarter),
+        (3, 0)
+    );
+
+    // A quarter later, they are in the prior quarter of the trend
+    let opts = AnalysisOptions {
+        fixed_now: Some(MONDAY + 95 * DAY),
+        ..opts
+    };
+    let stats = analyze(&repository, &opts).unwrap();
+    assert_eq!(
+        (stats.commits_last_quarter, stats.commits_prior_quarter),
+        (0, 3)
+    );
+}
+
+#[test]
+fn churn_of_merges() {
+    let (_dir, repository) = init_repository();
+    let root = commit_file(&repository, "README.md", "a\n", &ALICE, MONDAY);
+
+    // Bob adds a file on a branch, while Alice changes the README on the mainline and then merges the branch
+    let side = commit_file(&repository, "side.txt", "1\n2\n3\n", &BOB, MONDAY + DAY);
+    let side = repository.find_commit(side).unwrap();
+    let root = repository.find_object(root, None).unwrap();
+    repository
+        .reset(&root, git2::ResetType::Hard, None)
+        .unwrap();
+    let main = commit_file(&repository, "README.md", "b\n", &ALICE, MONDAY + 2 * DAY);
+    let main = repository.find_commit(main).unwrap();
+    let side_file = side.tree().unwrap().get_name("side.txt").unwrap().id();
+    let mut tree = repository.treebuilder(Some(&main.tree().unwrap())).unwrap();
+    tree.insert("side.txt", side_file, 0o100644).unwrap();
+    let tree = repository.find_tree(tree.write().unwrap()).unwrap();
+    let time = git2::Time::new(MONDAY + 3 * DAY, 0);
+    let alice = git2::Signature::new(ALICE.name, ALICE.email, &time).unwrap();
+    repository
+        .commit(
+            Some("HEAD"),
+            &alice,
+            &alice,
+            "Merge",
+            &tree,
+            &[&main, &side],
+        )
+        .unwrap();
+
+    let opts = AnalysisOptions {
+        extended_stats: true,
+        ..AnalysisOptions::default()
+    };
+    let stats = analyze(&repository, &opts).unwrap();
+    assert_eq!(stats.merge_commits, 1);
+    assert_eq!(
+        (stats.total_lines_inserted, stats.total_lines_removed),
+        (4, 1)
+    );
+
+    // The diff of the merge to Alice's commit contains Bob's file again
+    let opts = AnalysisOptions {
+        churn_merges: ChurnMerges::FirstParent,
+        ..opts
+    };
+    let stats = analyze(&repository, &opts).unwrap();
+    assert_eq!(
+        (stats.total_lines_inserted, stats.total_lines_removed),
+        (7, 1)
+    );
+    assert_eq!(stats.lines_per_author["Alice"], (4, 1));
+}
+
+#[test]
+fn author_timeline() {
+    let (_dir, repository) = init_repository();
+    fixture(&repository);
+
+    let opts = AnalysisOptions {
+        author_timeline: true,
+        ..AnalysisOptions::default()
+    };
+    let stats = analyze(&repository, &opts).unwrap();
+    let monday = MONDAY as u64;
+    let day = DAY as u64;
+    assert_eq!(stats.author_first_last["Alice"], (monday, monday + 5 * day));
+    assert_eq!(
+        stats.author_first_last["Bob"],
+        (monday + 2 * day, monday + 2 * day)
+    );
+}
+
+#[test]
+fn contributor_classes() {
+    let (_dir, repository) = init_repository();
+    fixture(&repository);
+
+    let opts = AnalysisOptions {
+        contributor_classes: Some(3),
+        ..AnalysisOptions::default()
+    };
+    let stats = analyze(&repository, &opts).unwrap();
+    assert!(stats.has_contributor_classes);
+    assert!(!stats.has_author_timeline);
+    assert_eq!(stats.min_core_commits, 3);
+    // The last commit per contributor decides whether they are recently active
+    assert_eq!(stats.author_first_last.len(), stats.contributors.len());
+}
+
+#[test]
+fn reverts_and_fixups() {
+    let (_dir, repository) = init_repository();
+    let messages = [
+        "Add parser",
+        "fixup! Add parser",
+        "squash! Add parser",
+        "Revert \"Add parser\"",
+        // Only the exact prefixes generated by git count
+        "revert the parser",
+        "Fixup! Add parser",
+    ];
+    for (day, message) in messages.iter().enumerate() {
+        let content = day.to_string();
+        let when = MONDAY + day as i64 * DAY;
+        commit_files(
+            &repository,
+            &[("a.txt", &content)],
+            &ALICE,
+            &ALICE,
+            when,
+            message,
+        );
+    }
+
+    let opts = AnalysisOptions {
+        message_stats: true,
+        ..AnalysisOptions::default()
+    };
+    let stats = analyze(&repository, &opts).unwrap();
+    assert_eq!(stats.revert_commits, 1);
+    assert_eq!(stats.fixup_commits, 2);
+}
+
+#[test]
+fn sampled_churn() {
+    let (_dir, repository) = init_repository();
+    for day in 0..40 {
+        let content = format!("{}\n", day);
+        commit_file(&repository, "a.txt", &content, &ALICE, MONDAY + day * DAY);
+    }
+
+    let opts = AnalysisOptions {
+        extended_stats: true,
+        sample_rate: Some(0.5),
+        ..AnalysisOptions::default()
+    };
+    let stats = analyze(&repository, &opts).unwrap();
+    // Every commit but the first replaces one line
+    assert_eq!(stats.commit_count, 40);
+    let (sampled, diffable) = stats.churn_sample.unwrap();
+    assert_eq!(diffable, 40);
+    assert!(sampled > 0 && sampled < 40);
+    let lines = stats.total_lines_inserted as f64;
+    assert!((lines - 39.0).abs() <= 3.0, "estimated {} lines", lines);
+
+    // The same commits are sampled again
+    let again = analyze(&repository, &opts).unwrap();
+    assert_eq!(again.churn_sample, stats.churn_sample);
+    assert_eq!(again.total_lines_inserted, stats.total_lines_inserted);
+}
+
+#[test]
+fn churn_of_diff_algorithms() {
+    let (_dir, repository) = init_repository();
+    // Lines that are matched up differently by the algorithms
+    commit_file(
+        &repository,
+        "a.txt",
+        "a\nb\na\na\na\nc\nc\nu\n",
+        &ALICE,
+        MONDAY,
+    );
+    commit_file(
+        &repository,
+        "a.txt",
+        "c\na\nb\nb\nc\nu\na\na\n",
+        &ALICE,
+        MONDAY + DAY,
+    );
+
+    let churn = |diff_algorithm| {
+        let opts = AnalysisOptions {
+            extended_stats: true,
+            diff_algorithm,
+            ..AnalysisOptions::default()
+        };
+        let stats = analyze(&repository, &opts).unwrap();
+        assert_eq!(stats.diff_algorithm, diff_algorithm);
+        (stats.total_lines_inserted, stats.total_lines_removed)
+    };
+    assert_eq!(churn(DiffAlgorithm::Myers), (4, 4));
+    assert_eq!(churn(DiffAlgorithm::Patience), (5, 5));
+}
+
+#[test]
+fn churn_of_whitespace() {
+    let (_dir, repository) = init_repository();
+    commit_file(&repository, "a.txt", "a\nb\nc\n", &ALICE, MONDAY);
+    // Only adds trailing whitespace and a blank line further down
+    commit_file(&repository, "a.txt", "a  \nb\n\nc\n", &ALICE, MONDAY + DAY);
+
+    let churn = |ignore_whitespace, ignore_blank_lines| {
+        let opts = AnalysisOptions {
+            extended_stats: true,
+            ignore_whitespace,
+            ignore_blank_lines,
+            ..AnalysisOptions::default()
+        };
+        let stats = analyze(&repository, &opts).unwrap();
+        (stats.total_lines_inserted, stats.total_lines_removed)
+    };
+    assert_eq!(churn(false, false), (2, 1));
+    assert_eq!(churn(true, false), (1, 0));
+    assert_eq!(churn(false, true), (1, 1));
+    assert_eq!(churn(true, true), (0, 0));
+}
+
+#[test]
+fn empty_commits() {
+    let (_dir, repository) = init_repository();
+    fixture(&repository);
+    // Committing the same content again doesn't change the tree, like `git commit --allow-empty`
+    commit_file(&repository, "README.md", "a\nc\n", &BOB, MONDAY + 6 * DAY);
+
+    let opts = AnalysisOptions {
+        extended_stats: true,
+        ..AnalysisOptions::default()
+    };
+    let stats = analyze(&repository, &opts).unwrap();
+    assert_eq!((stats.commit_count, stats.empty_commits), (4, 1));
+
+    let opts = AnalysisOptions {
+        no_empty: true,
+        ..opts
+    };
+    let stats = analyze(&repository, &opts).unwrap();
+    assert!(stats.empty_excluded);
+    assert_eq!((stats.commit_count, stats.empty_commits), (3, 0));
+    assert_eq!(stats.contributors["Bob"], 1.0);
+}
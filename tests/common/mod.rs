@@ -0,0 +1,80 @@
+//! Helpers to build fixture repositories with a known history for the integration tests
+
+use git2::{Oid, Repository, Signature, Time};
+use std::path::Path;
+use tempfile::TempDir;
+
+/// Author of fixture commits
+pub struct Author<'a> {
+    pub name: &'a str,
+    pub email: &'a str,
+}
+
+pub const ALICE: Author = Author {
+    name: "Alice",
+    email: "alice@example.com",
+};
+pub const BOB: Author = Author {
+    name: "Bob",
+    email: "bob@example.com",
+};
+
+/// Creates an empty repository in a new temporary directory, which is removed once the directory is dropped
+pub fn init_repository() -> (TempDir, Repository) {
+    let dir = tempfile::tempdir().unwrap();
+    let repository = Repository::init(dir.path()).unwrap();
+    (dir, repository)
+}
+
+/// Writes a single file and commits it on top of HEAD, see [`commit_files`]
+pub fn commit_file(
+    repository: &Repository,
+    path: &str,
+    content: &str,
+    author: &Author,
+    when: i64,
+) -> Oid {
+    commit_files(
+        repository,
+        &[(path, content)],
+        author,
+        author,
+        when,
+        "Commit",
+    )
+}
+
+/// Writes the files into the working directory and commits them on top of HEAD.
+/// `when` is the Unix timestamp of the commit, which is made in UTC.
+/// The committer differs from the author for e.g. rebased or cherry-picked commits.
+pub fn commit_files(
+    repository: &Repository,
+    files: &[(&str, &str)],
+    author: &Author,
+    committer: &Author,
+    when: i64,
+    message: &str,
+) -> Oid {
+    let workdir = repository.workdir().unwrap();
+    let mut index = repository.index().unwrap();
+    for (path, content) in files {
+        let file = workdir.join(path);
+        std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+        std::fs::write(file, content).unwrap();
+        index.add_path(Path::new(path)).unwrap();
+    }
+    index.write().unwrap();
+    let tree = repository.find_tree(index.write_tree().unwrap()).unwrap();
+
+    let time = Time::new(when, 0);
+    let author = Signature::new(author.name, author.email, &time).unwrap();
+    let committer = Signature::new(committer.name, committer.email, &time).unwrap();
+    let parent = repository
+        .head()
+        .and_then(|head| head.peel_to_commit())
+        .ok();
+    let parents: Vec<&git2::Commit> = parent.iter().collect();
+    repository
+        .commit(Some("HEAD"), &author, &committer, message, &tree, &parents)
+        .unwrap()
+}